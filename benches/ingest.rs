@@ -0,0 +1,131 @@
+//! Measures the throughput of matching enter and exit events on synthetic traces, both alone and
+//! end to end through [`TraceModel::add_event`], which also builds the bars, statistics and
+//! summaries.
+//!
+//! Run with `cargo bench --bench ingest`.
+
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rtic_scope_api::{EventType, TaskAction};
+use rtic_scope_frontend_racer::{ingest::Ingest, trace::TraceModel};
+
+const EVENTS: usize = 10_000_000;
+
+enum Action {
+    Enter,
+    Exit,
+}
+
+/// Generates a trace of nested activations, where a task may only preempt tasks of a lower
+/// index, as in an RTIC application where the index is the priority.
+fn synthetic_trace(tasks: usize, events: usize) -> Vec<(usize, usize, Action)> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut running: Vec<usize> = vec![];
+    let mut timestamp = 0;
+    let mut trace = Vec::with_capacity(events);
+
+    while trace.len() < events {
+        timestamp += rng.gen_range(1..1000);
+        let lowest = match running.last() {
+            Some(task) => task + 1,
+            None => 0,
+        };
+        if lowest < tasks && (running.is_empty() || rng.gen_bool(0.4)) {
+            let task = rng.gen_range(lowest..tasks);
+            running.push(task);
+            trace.push((timestamp, task, Action::Enter));
+        } else if let Some(task) = running.pop() {
+            trace.push((timestamp, task, Action::Exit));
+        }
+    }
+
+    trace
+}
+
+fn print(
+    name: &str,
+    tasks: usize,
+    events: usize,
+    elapsed: Duration,
+    activations: usize,
+    open: usize,
+) {
+    println!(
+        "{:>6} {:>5} tasks: {} events in {:.3}s ({:.1} Mevents/s), {} activations, {} open",
+        name,
+        tasks,
+        events,
+        elapsed.as_secs_f64(),
+        events as f64 / elapsed.as_secs_f64() / 1e6,
+        activations,
+        open,
+    );
+}
+
+fn bench_ingest(names: &[String], trace: &[(usize, usize, Action)]) {
+    let start = Instant::now();
+    let mut ingest = Ingest::new();
+    let mut activations = 0;
+    for (timestamp, task, action) in trace {
+        match action {
+            Action::Enter => {
+                ingest.enter(&names[*task], *timestamp);
+            }
+            Action::Exit => {
+                if ingest.exit(&names[*task], *timestamp).is_some() {
+                    activations += 1;
+                }
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+    print(
+        "ingest",
+        names.len(),
+        trace.len(),
+        elapsed,
+        activations,
+        ingest.open_activations(),
+    );
+}
+
+/// Adds the events to a model like the timeline does, including creating the events, as they are
+/// deserialized one by one in the frontend as well.
+fn bench_model(names: &[String], trace: &[(usize, usize, Action)]) {
+    let start = Instant::now();
+    let mut model = TraceModel::new();
+    for (timestamp, task, action) in trace {
+        let action = match action {
+            Action::Enter => TaskAction::Entered,
+            Action::Exit => TaskAction::Exited,
+        };
+        model.add_event(
+            *timestamp,
+            EventType::Task {
+                name: names[*task].clone(),
+                action,
+            },
+        );
+    }
+    let elapsed = start.elapsed();
+    print(
+        "model",
+        names.len(),
+        trace.len(),
+        elapsed,
+        model.bar_count(),
+        model.open_activations(),
+    );
+}
+
+fn main() {
+    for &tasks in &[4, 64, 1024] {
+        let names = (0..tasks)
+            .map(|task| format!("task{}", task))
+            .collect::<Vec<_>>();
+        let trace = synthetic_trace(tasks, EVENTS);
+        bench_ingest(&names, &trace);
+        bench_model(&names, &trace);
+    }
+}
@@ -0,0 +1,238 @@
+//! A summary of a trace for automated checks, e.g. in hardware-in-the-loop CI.
+
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    chain::{Chain, Latency},
+    time::{self, TimeFormat},
+    trace::TraceModel,
+};
+
+/// The longest time an activation of a task may take.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadline {
+    pub task: String,
+    pub max_ns: u64,
+}
+
+impl FromStr for Deadline {
+    type Err = String;
+
+    /// Parses a deadline like `app::foo=50us`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (task, max) = text
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid deadline {:?}, expected <task>=<time>", text))?;
+        let max_ns = time::parse(max).ok_or_else(|| format!("Invalid time {:?}", max))?;
+        Ok(Self {
+            task: task.to_string(),
+            max_ns,
+        })
+    }
+}
+
+/// The statistics of a trace and all activations which missed their deadline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub tasks: Vec<TaskReport>,
+    pub overflows: usize,
+    pub unmatched_exits: usize,
+    pub open_activations: usize,
+    pub unknown_events: usize,
+    pub violations: Vec<Violation>,
+    /// The tasks with a deadline which do not appear in the trace, e.g. because of a typo.
+    pub missing_tasks: Vec<String>,
+    pub chains: Vec<ChainReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskReport {
+    pub name: String,
+    pub priority: Option<u8>,
+    pub activations: usize,
+    pub completed: usize,
+    pub total_ns: usize,
+    pub min_ns: Option<usize>,
+    pub max_ns: Option<usize>,
+    pub mean_ns: Option<f64>,
+    pub mean_period_ns: Option<f64>,
+}
+
+/// An activation which took longer than the deadline of its task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub task: String,
+    pub start_ns: usize,
+    pub duration_ns: usize,
+    pub deadline_ns: u64,
+}
+
+/// The end-to-end latencies of a chain of tasks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainReport {
+    pub chain: String,
+    #[serde(flatten)]
+    pub latency: Latency,
+}
+
+impl Report {
+    pub fn new(
+        model: &mut TraceModel,
+        deadlines: &[Deadline],
+        chains: &[Chain],
+    ) -> io::Result<Self> {
+        let mut violations = vec![];
+        let mut missing_tasks = vec![];
+        let bars = model.all_bars()?;
+        for deadline in deadlines {
+            let channel = match model.channel(&deadline.task) {
+                Some(channel) => channel,
+                None => {
+                    missing_tasks.push(deadline.task.clone());
+                    continue;
+                }
+            };
+            violations.extend(
+                bars.iter()
+                    .filter(|bar| bar.channel == channel)
+                    .filter(|bar| bar.duration() as u64 > deadline.max_ns)
+                    .map(|bar| Violation {
+                        task: deadline.task.clone(),
+                        start_ns: bar.start,
+                        duration_ns: bar.duration(),
+                        deadline_ns: deadline.max_ns,
+                    }),
+            );
+        }
+        violations.sort_by_key(|violation| violation.start_ns);
+
+        let tasks = model
+            .lanes()
+            .iter()
+            .zip(model.stats())
+            .map(|(lane, stats)| TaskReport {
+                name: lane.name.clone(),
+                priority: lane.priority,
+                activations: lane.activations,
+                completed: stats.completed,
+                total_ns: stats.total_ns,
+                min_ns: stats.min_ns,
+                max_ns: stats.max_ns,
+                mean_ns: stats.mean_ns(),
+                mean_period_ns: stats.mean_period_ns(),
+            })
+            .collect();
+
+        let chains = chains
+            .iter()
+            .map(|chain| {
+                Ok(ChainReport {
+                    chain: chain.to_string(),
+                    latency: Latency::new(&chain.instances(model)?),
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            tasks,
+            overflows: model.overflows(),
+            unmatched_exits: model.unmatched_exits(),
+            open_activations: model.open_activations(),
+            unknown_events: model.unknown_events(),
+            violations,
+            missing_tasks,
+            chains,
+        })
+    }
+
+    /// Writes the report as a human-readable table.
+    pub fn write_text(&self, mut out: impl Write, time_format: &TimeFormat) -> io::Result<()> {
+        let time = |ns: Option<f64>| {
+            ns.map(|ns| time_format.format(ns.round() as u64))
+                .unwrap_or_else(|| String::from("-"))
+        };
+        let name_width = self
+            .tasks
+            .iter()
+            .map(|task| task.name.len())
+            .chain([4])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            out,
+            "{:name_width$}  {:>8}  {:>11}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}",
+            "Task",
+            "Priority",
+            "Activations",
+            "Completed",
+            "Min",
+            "Mean",
+            "Max",
+            "Period",
+            name_width = name_width
+        )?;
+        for task in &self.tasks {
+            writeln!(
+                out,
+                "{:name_width$}  {:>8}  {:>11}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}",
+                task.name,
+                task.priority
+                    .map(|priority| priority.to_string())
+                    .unwrap_or_else(|| String::from("-")),
+                task.activations,
+                task.completed,
+                time(task.min_ns.map(|ns| ns as f64)),
+                time(task.mean_ns),
+                time(task.max_ns.map(|ns| ns as f64)),
+                time(task.mean_period_ns),
+                name_width = name_width
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Overflows: {}", self.overflows)?;
+        writeln!(out, "Unmatched exits: {}", self.unmatched_exits)?;
+        writeln!(out, "Open activations: {}", self.open_activations)?;
+        writeln!(out, "Unknown events: {}", self.unknown_events)?;
+        writeln!(out, "Deadline violations: {}", self.violations.len())?;
+        for violation in &self.violations {
+            writeln!(
+                out,
+                "    {} at {} took {}, deadline {}",
+                violation.task,
+                time_format.format(violation.start_ns as u64),
+                time_format.format(violation.duration_ns as u64),
+                time_format.format(violation.deadline_ns)
+            )?;
+        }
+        for task in &self.missing_tasks {
+            writeln!(
+                out,
+                "The task {} of a deadline does not appear in the trace",
+                task
+            )?;
+        }
+        for chain in &self.chains {
+            let latency = &chain.latency;
+            writeln!(
+                out,
+                "Chain {}: {} instances, latency min {}, mean {}, median {}, p90 {}, p99 {}, max {}",
+                chain.chain,
+                latency.instances,
+                time(latency.min_ns.map(|ns| ns as f64)),
+                time(latency.mean_ns),
+                time(latency.median_ns.map(|ns| ns as f64)),
+                time(latency.p90_ns.map(|ns| ns as f64)),
+                time(latency.p99_ns.map(|ns| ns as f64)),
+                time(latency.max_ns.map(|ns| ns as f64)),
+            )?;
+        }
+        out.flush()
+    }
+}
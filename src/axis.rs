@@ -0,0 +1,66 @@
+use std::ops::Range;
+
+/// A tick of a time axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub ns: u64,
+    /// Whether this is a major tick, which gets a label.
+    pub major: bool,
+}
+
+/// The spacing of the ticks of a time axis.
+///
+/// Major ticks are placed at multiples of 1, 2 or 5 × 10^n ns, so their labels are round
+/// values. Minor ticks subdivide them into four or five parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Axis {
+    pub major_ns: u64,
+    /// Equal to `major_ns` if the major ticks cannot be subdivided.
+    pub minor_ns: u64,
+}
+
+impl Axis {
+    /// Chooses the finest spacing at which major ticks are at least `min_spacing` px apart, given
+    /// the scale in px per ns.
+    pub fn new(zoom: f64, min_spacing: f64) -> Self {
+        let mut magnitude = 1u64;
+        loop {
+            for mantissa in [1, 2, 5] {
+                let major_ns = mantissa * magnitude;
+                // The next magnitude would not fit into an u64.
+                let is_last = mantissa == 5 && magnitude > u64::MAX / 100;
+                if major_ns as f64 * zoom >= min_spacing || is_last {
+                    return Self {
+                        major_ns,
+                        minor_ns: Self::minor_ns(mantissa, magnitude),
+                    };
+                }
+            }
+            magnitude *= 10;
+        }
+    }
+
+    fn minor_ns(mantissa: u64, magnitude: u64) -> u64 {
+        match mantissa {
+            1 if magnitude >= 10 => magnitude / 5,
+            2 if magnitude >= 10 => magnitude / 2,
+            2 => 1,
+            // 5 × 10^n is split into 10^n, and 1ns cannot be split at all.
+            _ => magnitude,
+        }
+    }
+
+    /// Returns all ticks within the given range in ns.
+    pub fn ticks(&self, range: Range<f64>) -> impl Iterator<Item = Tick> {
+        let Self { major_ns, minor_ns } = *self;
+        let first = (range.start.max(0.0) / minor_ns as f64).ceil() as u64;
+        let end = range.end;
+        (first..)
+            .map_while(move |index| index.checked_mul(minor_ns))
+            .take_while(move |&ns| (ns as f64) <= end)
+            .map(move |ns| Tick {
+                ns,
+                major: ns % major_ns == 0,
+            })
+    }
+}
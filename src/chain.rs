@@ -0,0 +1,154 @@
+//! Causal chains of tasks, e.g. an interrupt handler which spawns a software task which spawns
+//! another one, and the end-to-end latency of their instances.
+
+use std::{fmt, io, str::FromStr};
+
+use serde::Serialize;
+
+use crate::trace::{Bar, TraceModel};
+
+/// Tasks which are activated one after another, written like `UART0 -> parse -> send_reply`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub tasks: Vec<String>,
+}
+
+impl FromStr for Chain {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let tasks = text
+            .split("->")
+            .flat_map(|tasks| tasks.split('→'))
+            .map(|task| task.trim().to_string())
+            .collect::<Vec<_>>();
+        if tasks.len() < 2 || tasks.iter().any(|task| task.is_empty()) {
+            return Err(format!(
+                "Invalid chain {:?}, expected e.g. UART0 -> parse -> send_reply",
+                text
+            ));
+        }
+        Ok(Self { tasks })
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tasks.join(" -> "))
+    }
+}
+
+/// The activations which make up one pass through a chain, one per task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub bars: Vec<Bar>,
+}
+
+impl Instance {
+    /// Returns the time from the start of the first activation to the end of the last one.
+    pub fn latency_ns(&self) -> usize {
+        match (self.bars.first(), self.bars.last()) {
+            (Some(first), Some(last)) => last.end.saturating_sub(first.start),
+            _ => 0,
+        }
+    }
+}
+
+impl Chain {
+    /// Matches the completed activations of the tasks to instances of the chain.
+    ///
+    /// Each activation of the first task starts an instance. It continues with the first unused
+    /// activation of the next task which starts after it, but before the following activation of
+    /// the same task, which would have caused it instead. Activations of the first task which
+    /// are not followed by the whole chain are left out.
+    pub fn instances(&self, model: &mut TraceModel) -> io::Result<Vec<Instance>> {
+        let bars = model.all_bars()?;
+        let mut tasks = vec![];
+        for task in &self.tasks {
+            match model.channel(task) {
+                Some(channel) => tasks.push(
+                    bars.iter()
+                        .filter(|bar| bar.channel == channel)
+                        .copied()
+                        .collect::<Vec<_>>(),
+                ),
+                None => return Ok(vec![]),
+            }
+        }
+
+        // The index of the first activation of each task which was not matched yet.
+        let mut unused = vec![0; tasks.len()];
+        let mut instances = vec![];
+        'first: for index in 0..tasks.first().map_or(0, Vec::len) {
+            let mut indices = vec![index];
+            for step in 1..tasks.len() {
+                let cause = &tasks[step - 1];
+                let cause_index = indices[step - 1];
+                let next_cause = cause.get(cause_index + 1).map(|bar| bar.start);
+                let start = cause[cause_index].start;
+
+                let candidates = &tasks[step][unused[step]..];
+                let offset = candidates.partition_point(|bar| bar.start < start);
+                match candidates.get(offset) {
+                    Some(bar) if next_cause.map_or(true, |next| bar.start < next) => {
+                        indices.push(unused[step] + offset)
+                    }
+                    _ => continue 'first,
+                }
+            }
+
+            for (step, &index) in indices.iter().enumerate() {
+                unused[step] = index + 1;
+            }
+            instances.push(Instance {
+                bars: indices
+                    .iter()
+                    .zip(&tasks)
+                    .map(|(&index, bars)| bars[index])
+                    .collect(),
+            });
+        }
+        Ok(instances)
+    }
+}
+
+/// The distribution of the end-to-end latencies of the instances of a chain.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Latency {
+    pub instances: usize,
+    pub min_ns: Option<usize>,
+    pub mean_ns: Option<f64>,
+    pub median_ns: Option<usize>,
+    pub p90_ns: Option<usize>,
+    pub p99_ns: Option<usize>,
+    pub max_ns: Option<usize>,
+}
+
+impl Latency {
+    pub fn new(instances: &[Instance]) -> Self {
+        let mut latencies = instances
+            .iter()
+            .map(Instance::latency_ns)
+            .collect::<Vec<_>>();
+        latencies.sort_unstable();
+
+        // The nearest-rank percentile.
+        let percentile = |percent: usize| {
+            let rank = (percent * latencies.len()).div_ceil(100);
+            latencies.get(rank.max(1) - 1).copied()
+        };
+        let mean_ns = match latencies.len() {
+            0 => None,
+            count => Some(latencies.iter().sum::<usize>() as f64 / count as f64),
+        };
+        Self {
+            instances: latencies.len(),
+            min_ns: latencies.first().copied(),
+            mean_ns,
+            median_ns: percentile(50),
+            p90_ns: percentile(90),
+            p99_ns: percentile(99),
+            max_ns: latencies.last().copied(),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Export to the [Trace Event Format] of chrome://tracing, which Perfetto can open as well.
+//!
+//! [Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use std::io::{self, Write};
+
+use serde_json::json;
+
+use crate::trace::TraceModel;
+
+/// The process all tasks are shown in.
+const PID: usize = 1;
+
+/// Writes all bars of the trace as complete events, with one thread per task, and all overflows
+/// as global instant events.
+pub fn write(model: &mut TraceModel, mut out: impl Write) -> io::Result<()> {
+    let bars = model.all_bars()?;
+
+    let mut events = vec![json!({
+        "name": "process_name",
+        "ph": "M",
+        "pid": PID,
+        "args": { "name": "RTIC" },
+    })];
+    for (channel, lane) in model.lanes().iter().enumerate() {
+        events.push(json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": PID,
+            "tid": channel,
+            "args": { "name": lane.name },
+        }));
+        if let Some(priority) = lane.priority {
+            // Sort the threads by priority, highest first.
+            events.push(json!({
+                "name": "thread_sort_index",
+                "ph": "M",
+                "pid": PID,
+                "tid": channel,
+                "args": { "sort_index": -(priority as i64) },
+            }));
+        }
+    }
+    for bar in bars {
+        events.push(json!({
+            "name": model.lanes()[bar.channel].name,
+            "cat": "task",
+            "ph": "X",
+            "ts": micros(bar.start),
+            "dur": micros(bar.duration()),
+            "pid": PID,
+            "tid": bar.channel,
+        }));
+    }
+    for &timestamp in model.overflow_timestamps() {
+        events.push(json!({
+            "name": "overflow",
+            "cat": "trace",
+            "ph": "i",
+            "s": "g",
+            "ts": micros(timestamp),
+            "pid": PID,
+        }));
+    }
+
+    serde_json::to_writer(
+        &mut out,
+        &json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ns",
+        }),
+    )?;
+    out.flush()
+}
+
+/// Timestamps are in µs, with the ns as decimals.
+fn micros(ns: usize) -> f64 {
+    ns as f64 / 1e3
+}
@@ -0,0 +1,198 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    os::unix::net::UnixListener,
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context};
+use rtic_scope_frontend_racer::{
+    analyze::{Deadline, Report},
+    chain::Chain,
+    rules,
+    time::TimeFormat,
+    trace::TraceModel,
+};
+
+use crate::export;
+
+const USAGE: &str = "\
+Usage:
+    racer                             Open the timeline and wait for a trace on a socket
+    racer export <trace> <output>     Convert a recorded trace
+    racer analyze [options] [trace]   Print the statistics of a trace without opening a window
+    racer check [options] <rules> [trace]
+                                      Check the timing rules in a file against a trace
+
+A recorded trace has one JSON-serialized event chunk per line, as they are sent to the socket.
+Use - as the trace to read it from stdin.
+
+The format of the output is chosen by its extension:
+    .json        Chrome Trace Event Format, for chrome://tracing and Perfetto
+    .vcd         Value Change Dump, for waveform viewers like GTKWave
+    .csv         One row per completed task activation
+    .stats.csv   One row with the statistics per task
+
+Options of analyze:
+    --json                      Print the report as JSON instead of a table
+    --deadline <task>=<time>    Report activations of the task which take longer, e.g.
+                                --deadline app::foo=50us. May be given multiple times.
+    --chain <tasks>             Report the end-to-end latency of a chain of tasks which are
+                                activated one after another, e.g.
+                                --chain 'UART0 -> parse -> send_reply'. May be given multiple
+                                times.
+    --socket                    Instead of a recorded trace, wait for one on a socket like the
+                                timeline does. The path of the socket is printed first.
+
+Options of check:
+    --junit <path>              Also write the results as JUnit XML for CI servers
+    --socket                    Like for analyze
+
+A rules file has one rule per line, e.g.
+    task app::foo max_duration < 50us
+    task app::tick period 1ms +-5%
+    task app::reply follows app::parse within 10us
+    no overflows
+
+analyze and check exit with an error if any deadline was missed or rule failed.
+";
+
+/// Runs a headless command with the given arguments, excluding the program name.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    match args {
+        [help] if help == "-h" || help == "--help" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        [command, trace, output] if command == "export" => {
+            let mut model = read_trace(trace)?;
+            export::save_trace(&mut model, Path::new(output))
+        }
+        [command, options @ ..] if command == "analyze" => analyze(options),
+        [command, options @ ..] if command == "check" => check(options),
+        _ => bail!("Invalid arguments {:?}\n\n{}", args, USAGE),
+    }
+}
+
+fn analyze(options: &[String]) -> anyhow::Result<()> {
+    let mut is_json = false;
+    let mut is_socket = false;
+    let mut deadlines = vec![];
+    let mut chains = vec![];
+    let mut trace = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--json" => is_json = true,
+            "--socket" => is_socket = true,
+            "--deadline" => {
+                let deadline = options
+                    .next()
+                    .context("Missing deadline after --deadline")?;
+                deadlines.push(deadline.parse::<Deadline>().map_err(|e| anyhow!(e))?);
+            }
+            "--chain" => {
+                let chain = options.next().context("Missing tasks after --chain")?;
+                chains.push(chain.parse::<Chain>().map_err(|e| anyhow!(e))?);
+            }
+            _ if option.starts_with("--") => bail!("Unknown option {:?}\n\n{}", option, USAGE),
+            _ if trace.is_none() => trace = Some(option.as_str()),
+            _ => bail!("Unexpected argument {:?}\n\n{}", option, USAGE),
+        }
+    }
+
+    let mut model = match (trace, is_socket) {
+        (None, true) => receive_trace()?,
+        (Some(path), false) => read_trace(path)?,
+        _ => bail!("Expected either a trace or --socket\n\n{}", USAGE),
+    };
+    let report = Report::new(&mut model, &deadlines, &chains)?;
+    if is_json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.write_text(io::stdout().lock(), &TimeFormat::default())?;
+    }
+
+    if !report.missing_tasks.is_empty() {
+        bail!(
+            "The tasks {:?} of the deadlines do not appear in the trace",
+            report.missing_tasks
+        );
+    }
+    if !report.violations.is_empty() {
+        bail!(
+            "{} activations missed their deadline",
+            report.violations.len()
+        );
+    }
+    Ok(())
+}
+
+fn check(options: &[String]) -> anyhow::Result<()> {
+    let mut is_socket = false;
+    let mut junit = None;
+    let mut paths = vec![];
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--socket" => is_socket = true,
+            "--junit" => junit = Some(options.next().context("Missing path after --junit")?),
+            _ if option.starts_with("--") => bail!("Unknown option {:?}\n\n{}", option, USAGE),
+            _ => paths.push(option.as_str()),
+        }
+    }
+
+    let (rules_path, trace) = match (&paths[..], is_socket) {
+        ([rules], true) => (rules, None),
+        ([rules, trace], false) => (rules, Some(trace)),
+        _ => bail!(
+            "Expected a rules file and either a trace or --socket\n\n{}",
+            USAGE
+        ),
+    };
+    let text = std::fs::read_to_string(rules_path)
+        .with_context(|| format!("Failed to read {}", rules_path))?;
+    let rules = rules::parse(&text).with_context(|| format!("Failed to parse {}", rules_path))?;
+    let mut model = match trace {
+        Some(path) => read_trace(path)?,
+        None => receive_trace()?,
+    };
+
+    let outcomes = rules::check(&rules, &mut model, &TimeFormat::default())?;
+    rules::write_text(&outcomes, io::stdout().lock())?;
+    if let Some(path) = junit {
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path))?;
+        rules::write_junit(&outcomes, BufWriter::new(file))?;
+    }
+
+    let failed = outcomes.iter().filter(|outcome| !outcome.is_pass()).count();
+    if failed > 0 {
+        bail!("{} rules failed", failed);
+    }
+    Ok(())
+}
+
+/// Waits for a backend to connect to a new socket and reads the trace it sends until it
+/// disconnects.
+fn receive_trace() -> anyhow::Result<TraceModel> {
+    let socket_dir = tempfile::TempDir::new()?;
+    let socket_path = socket_dir.path().join("rtic-scope-frontend2.socket");
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Failed to bind {}", socket_path.display()))?;
+    // The backend reads the path of the socket from the first line of the output.
+    println!("{}", socket_path.display());
+
+    let (stream, _) = listener.accept()?;
+    TraceModel::read(BufReader::new(stream)).context("Failed to read the trace from the socket")
+}
+
+/// Reads a recorded trace from the file at the given path, or from stdin if it is `-`.
+pub fn read_trace(path: &str) -> anyhow::Result<TraceModel> {
+    let model = if path == "-" {
+        TraceModel::read(io::stdin().lock())
+    } else {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path))?;
+        TraceModel::read(BufReader::new(file))
+    };
+    model.with_context(|| format!("Failed to read the trace {}", path))
+}
@@ -0,0 +1,105 @@
+//! Comparison of two traces, e.g. of the same application before and after a change.
+
+use std::io;
+
+use crate::trace::{TaskStats, TraceModel};
+
+/// How the second trace is shifted in time to line up with the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Alignment {
+    /// The first events of both traces coincide.
+    Start,
+    /// The starts of the given completed activation of a task coincide, counted from zero.
+    Activation { task: String, index: usize },
+    /// The second trace is shifted by the given number of ns.
+    Manual(i64),
+}
+
+impl Alignment {
+    /// Returns the offset in ns which is added to the timestamps of `other` to line them up with
+    /// `base`, or `None` if either trace lacks the event to align on.
+    pub fn offset(&self, base: &mut TraceModel, other: &mut TraceModel) -> io::Result<Option<i64>> {
+        let (base_ns, other_ns) = match self {
+            Alignment::Start => match (base.range(), other.range()) {
+                (Some((base, _)), Some((other, _))) => (base, other),
+                _ => return Ok(None),
+            },
+            Alignment::Activation { task, index } => {
+                match (
+                    activation(base, task, *index)?,
+                    activation(other, task, *index)?,
+                ) {
+                    (Some(base), Some(other)) => (base, other),
+                    _ => return Ok(None),
+                }
+            }
+            Alignment::Manual(offset) => return Ok(Some(*offset)),
+        };
+        Ok(Some(base_ns as i64 - other_ns as i64))
+    }
+}
+
+/// Returns the start of the given completed activation of the task.
+fn activation(model: &mut TraceModel, task: &str, index: usize) -> io::Result<Option<usize>> {
+    let channel = match model.channel(task) {
+        Some(channel) => channel,
+        None => return Ok(None),
+    };
+    Ok(model
+        .all_bars()?
+        .into_iter()
+        .filter(|bar| bar.channel == channel)
+        .nth(index)
+        .map(|bar| bar.start))
+}
+
+/// The statistics of a task in both traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskDelta {
+    pub name: String,
+    /// Empty statistics if the task does not appear in the trace.
+    pub base: TaskStats,
+    pub other: TaskStats,
+}
+
+impl TaskDelta {
+    /// Returns how many more activations completed in the other trace.
+    pub fn completed(&self) -> i64 {
+        self.other.completed as i64 - self.base.completed as i64
+    }
+
+    /// Returns how much longer an activation takes in the other trace on average, in ns.
+    pub fn mean_ns(&self) -> Option<f64> {
+        Some(self.other.mean_ns()? - self.base.mean_ns()?)
+    }
+
+    /// Returns how much longer the longest activation is in the other trace, in ns.
+    pub fn max_ns(&self) -> Option<i64> {
+        Some(self.other.max_ns? as i64 - self.base.max_ns? as i64)
+    }
+}
+
+/// Matches the tasks of both traces by name, in the order of the base trace followed by the tasks
+/// which only appear in the other trace.
+pub fn stats_delta(base: &TraceModel, other: &TraceModel) -> Vec<TaskDelta> {
+    let stats = |model: &TraceModel, name: &str| {
+        model
+            .channel(name)
+            .map(|channel| model.stats()[channel])
+            .unwrap_or_default()
+    };
+
+    let names = base.lanes().iter().chain(
+        other
+            .lanes()
+            .iter()
+            .filter(|lane| base.channel(&lane.name).is_none()),
+    );
+    names
+        .map(|lane| TaskDelta {
+            name: lane.name.clone(),
+            base: stats(base, &lane.name),
+            other: stats(other, &lane.name),
+        })
+        .collect()
+}
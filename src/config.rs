@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use rtic_scope_frontend_racer::{
+    layout::{self, LaneOrder},
+    retention::Retention,
+    time::TimeFormat,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::style::Theme;
+
+/// User configuration which is persisted across sessions.
+///
+/// The configuration is read from `$RACER_CONFIG` if set, otherwise from
+/// `$XDG_CONFIG_HOME/racer/config.json` or `$HOME/.config/racer/config.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Color overrides for tasks, as a map of task name to a `#rrggbb` color.
+    pub colors: HashMap<String, String>,
+    pub theme: Theme,
+    pub retention: Retention,
+    pub time_format: TimeFormat,
+    pub lane_order: LaneOrder,
+    /// The description of the application the task priorities are read from, see
+    /// [`load_priorities`].
+    pub app: Option<PathBuf>,
+}
+
+/// Reads the priorities of tasks from the source of an RTIC application, or from a `.json` file
+/// with a map of task names to priorities.
+pub fn load_priorities(path: &Path) -> anyhow::Result<HashMap<String, u8>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    } else {
+        Ok(layout::app_priorities(&contents))
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("RACER_CONFIG") {
+            return Some(path.into());
+        }
+
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("racer").join("config.json"))
+    }
+
+    /// Loads the configuration, falling back to the defaults if there is no configuration file.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().context("Could not determine the configuration path")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,63 @@
+//! Export to comma-separated values, for spreadsheets and data frames.
+
+use std::io::{self, Write};
+
+use crate::trace::TraceModel;
+
+/// Writes one row per completed activation, sorted by their start.
+pub fn write_bars(model: &mut TraceModel, mut out: impl Write) -> io::Result<()> {
+    let bars = model.all_bars()?;
+
+    writeln!(out, "task,start_ns,end_ns,duration_ns,channel")?;
+    for bar in bars {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            quote(&model.lanes()[bar.channel].name),
+            bar.start,
+            bar.end,
+            bar.duration(),
+            bar.channel
+        )?;
+    }
+    out.flush()
+}
+
+/// Writes one row with the summary statistics per task. Columns are empty where a statistic is
+/// not defined, e.g. the period of a task which completed once.
+pub fn write_stats(model: &mut TraceModel, mut out: impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "task,channel,priority,activations,completed,total_ns,min_ns,max_ns,mean_ns,mean_period_ns"
+    )?;
+    for (channel, (lane, stats)) in model.lanes().iter().zip(model.stats()).enumerate() {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{}",
+            quote(&lane.name),
+            channel,
+            optional(lane.priority),
+            lane.activations,
+            stats.completed,
+            stats.total_ns,
+            optional(stats.min_ns),
+            optional(stats.max_ns),
+            optional(stats.mean_ns()),
+            optional(stats.mean_period_ns())
+        )?;
+    }
+    out.flush()
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quotes a field if it contains separators, quotes or line breaks.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    fmt::Write,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use iced::Color;
+use rtic_scope_frontend_racer::{chrome, csv, trace::TraceModel, vcd, xml::escape};
+
+/// The fonts which are tried in order for text, as the generic `sans-serif` family is not
+/// installed everywhere.
+const FONT_FAMILY: &str = "Helvetica, Arial, DejaVu Sans, sans-serif";
+
+#[derive(Debug, Clone, Copy)]
+pub enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+#[derive(Debug, Clone)]
+pub enum Item {
+    Rectangle {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        color: Color,
+    },
+    /// Text which is vertically centered on `y`.
+    Text {
+        x: f32,
+        y: f32,
+        content: String,
+        size: f32,
+        color: Color,
+        anchor: Anchor,
+    },
+}
+
+/// A drawing which does not depend on the canvas, so it can be written to a file at any size.
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    pub background: Color,
+    pub items: Vec<Item>,
+}
+
+impl Scene {
+    pub fn new(width: f32, height: f32, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            background,
+            items: vec![],
+        }
+    }
+
+    pub fn rectangle(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.items.push(Item::Rectangle {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), color: Color) {
+        self.items.push(Item::Line { from, to, color });
+    }
+
+    pub fn text(
+        &mut self,
+        x: f32,
+        y: f32,
+        content: impl Into<String>,
+        size: f32,
+        color: Color,
+        anchor: Anchor,
+    ) {
+        self.items.push(Item::Text {
+            x,
+            y,
+            content: content.into(),
+            size,
+            color,
+            anchor,
+        });
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        // Writing to a String cannot fail.
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.width,
+            h = self.height,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" {}/>"#,
+            self.width,
+            self.height,
+            paint("fill", self.background)
+        );
+        for item in &self.items {
+            let _ = match item {
+                Item::Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                    x,
+                    y,
+                    width,
+                    height,
+                    paint("fill", *color)
+                ),
+                Item::Line { from, to, color } => writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {}/>"#,
+                    from.0,
+                    from.1,
+                    to.0,
+                    to.1,
+                    paint("stroke", *color)
+                ),
+                Item::Text {
+                    x,
+                    y,
+                    content,
+                    size,
+                    color,
+                    anchor,
+                } => writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-family="{}" font-size="{}" text-anchor="{}" dominant-baseline="central" {}>{}</text>"#,
+                    x,
+                    y,
+                    FONT_FAMILY,
+                    size,
+                    match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    },
+                    paint("fill", *color),
+                    escape(content)
+                ),
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the scene to a PNG with one pixel per unit of the scene.
+    pub fn to_png(&self) -> anyhow::Result<Vec<u8>> {
+        let mut options = usvg::Options::default();
+        options.fontdb.load_system_fonts();
+        let tree = usvg::Tree::from_str(&self.to_svg(), &options.to_ref())?;
+
+        let width = self.width.ceil() as u32;
+        let height = self.height.ceil() as u32;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .with_context(|| format!("Invalid image size {}x{}", width, height))?;
+        resvg::render(
+            &tree,
+            usvg::FitTo::Original,
+            tiny_skia::Transform::default(),
+            pixmap.as_mut(),
+        )
+        .context("Failed to render the image")?;
+        Ok(pixmap.encode_png()?)
+    }
+
+    /// Writes the scene to an SVG or PNG file, depending on the extension of the path.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => self.to_svg().into_bytes(),
+            Some("png") => self.to_png()?,
+            _ => bail!(
+                "Unsupported image format {}, use .svg or .png",
+                path.display()
+            ),
+        };
+        fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Returns whether the path names an image, which is written from a [`Scene`], rather than
+/// trace data.
+pub fn is_image(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("svg") | Some("png")
+    )
+}
+
+/// Writes the trace in the format given by the extension of the path.
+pub fn save_trace(model: &mut TraceModel, path: &Path) -> anyhow::Result<()> {
+    let is_stats = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".stats.csv"));
+    let write: fn(&mut TraceModel, BufWriter<File>) -> io::Result<()> =
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => chrome::write,
+            Some("vcd") => vcd::write,
+            Some("csv") if is_stats => csv::write_stats,
+            Some("csv") => csv::write_bars,
+            _ => bail!(
+                "Unsupported trace format {}, use .json, .vcd or .csv",
+                path.display()
+            ),
+        };
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    write(model, BufWriter::new(file))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b] = [color.r, color.g, color.b].map(|c| (c * 255.0).round() as u8);
+    if color.a >= 1.0 {
+        format!(r##"{}="#{:02x}{:02x}{:02x}""##, attribute, r, g, b)
+    } else {
+        format!(
+            r##"{0}="#{1:02x}{2:02x}{3:02x}" {0}-opacity="{4}""##,
+            attribute, r, g, b, color.a
+        )
+    }
+}
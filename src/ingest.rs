@@ -0,0 +1,79 @@
+use rustc_hash::FxHashMap;
+
+/// A completed activation of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Activation {
+    pub channel: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Matches task enter and exit events into activations.
+///
+/// Tasks are assigned channels in the order they first appear. Lookups by name are hashed and
+/// every channel keeps its own stack of open activations, so the cost of an event does not depend
+/// on the number of tasks or the number of activations which have not exited yet.
+#[derive(Debug, Default)]
+pub struct Ingest {
+    channels: FxHashMap<String, usize>,
+    /// The start timestamps of the open activations of each channel, innermost last.
+    open: Vec<Vec<usize>>,
+}
+
+impl Ingest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the channel of the given task, assigning the next free one if it is new.
+    pub fn channel(&mut self, name: &str) -> usize {
+        if let Some(&channel) = self.channels.get(name) {
+            return channel;
+        }
+
+        let channel = self.open.len();
+        self.channels.insert(name.to_string(), channel);
+        self.open.push(vec![]);
+        channel
+    }
+
+    /// Returns the channel of the given task, if it appeared before.
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.channels.get(name).copied()
+    }
+
+    /// Opens an activation of the given task and returns its channel.
+    pub fn enter(&mut self, name: &str, timestamp: usize) -> usize {
+        let channel = self.channel(name);
+        self.open[channel].push(timestamp);
+        channel
+    }
+
+    /// Closes the innermost open activation of the given task which started before the timestamp.
+    ///
+    /// Returns `None` if the task has no such activation, e.g. because its enter event was lost.
+    pub fn exit(&mut self, name: &str, timestamp: usize) -> Option<Activation> {
+        let channel = *self.channels.get(name)?;
+        let open = &mut self.open[channel];
+        // This is the last activation unless timestamps arrived out of order.
+        let index = open.iter().rposition(|&start| start < timestamp)?;
+        Some(Activation {
+            channel,
+            start: open.remove(index),
+            end: timestamp,
+        })
+    }
+
+    /// Returns the channels and start timestamps of the activations which have not exited yet.
+    pub fn open(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.open
+            .iter()
+            .enumerate()
+            .flat_map(|(channel, starts)| starts.iter().map(move |&start| (channel, start)))
+    }
+
+    /// Returns the number of activations which have not exited yet.
+    pub fn open_activations(&self) -> usize {
+        self.open.iter().map(Vec::len).sum()
+    }
+}
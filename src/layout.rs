@@ -0,0 +1,184 @@
+//! The arrangement of the tasks in the lanes of the timeline, and the task priorities it is
+//! based on.
+
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::trace::Lane;
+
+/// How the tasks are arranged in lanes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LaneOrder {
+    /// One lane per task, in the order the tasks first appeared.
+    #[default]
+    Appearance,
+    /// One lane per task, grouped from the highest priority to the lowest. Tasks of unknown
+    /// priority come last.
+    Priority,
+    /// One lane per priority level, which contains all tasks of that priority. Tasks of unknown
+    /// priority keep their own lanes at the end.
+    PriorityLevel,
+}
+
+impl LaneOrder {
+    pub const ALL: [LaneOrder; 3] = [
+        LaneOrder::Appearance,
+        LaneOrder::Priority,
+        LaneOrder::PriorityLevel,
+    ];
+}
+
+impl fmt::Display for LaneOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaneOrder::Appearance => write!(f, "Lanes by appearance"),
+            LaneOrder::Priority => write!(f, "Lanes by priority"),
+            LaneOrder::PriorityLevel => write!(f, "Lane per priority"),
+        }
+    }
+}
+
+/// A lane of the timeline with the tasks drawn in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// The priority of all tasks of the lane, if known.
+    pub priority: Option<u8>,
+    /// The channels of the tasks, in the order they first appeared.
+    pub channels: Vec<usize>,
+}
+
+/// The lane each task is drawn in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    order: LaneOrder,
+    rows: Vec<Row>,
+    /// The index of the row of each channel.
+    row_of: Vec<usize>,
+}
+
+impl Layout {
+    pub fn new(lanes: &[Lane], order: LaneOrder) -> Self {
+        let mut channels = (0..lanes.len()).collect::<Vec<_>>();
+        if order != LaneOrder::Appearance {
+            // Unknown priorities sort last, and the sort is stable, so ties keep their order.
+            channels.sort_by_key(|&channel| match lanes[channel].priority {
+                Some(priority) => (0, u8::MAX - priority),
+                None => (1, 0),
+            });
+        }
+
+        let mut rows: Vec<Row> = vec![];
+        for channel in channels {
+            let priority = lanes[channel].priority;
+            match rows.last_mut() {
+                Some(row)
+                    if order == LaneOrder::PriorityLevel
+                        && priority.is_some()
+                        && row.priority == priority =>
+                {
+                    row.channels.push(channel)
+                }
+                _ => rows.push(Row {
+                    priority,
+                    channels: vec![channel],
+                }),
+            }
+        }
+
+        let mut row_of = vec![0; lanes.len()];
+        for (index, row) in rows.iter().enumerate() {
+            for &channel in &row.channels {
+                row_of[channel] = index;
+            }
+        }
+        Self {
+            order,
+            rows,
+            row_of,
+        }
+    }
+
+    pub fn order(&self) -> LaneOrder {
+        self.order
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Returns the index of the row the task of the given channel is drawn in.
+    pub fn row(&self, channel: usize) -> usize {
+        self.row_of[channel]
+    }
+
+    /// Returns whether the row has a different priority than the one above it, so a separator
+    /// is drawn between the groups of priorities.
+    pub fn starts_group(&self, row: usize) -> bool {
+        self.order != LaneOrder::Appearance
+            && row > 0
+            && self.rows[row].priority != self.rows[row - 1].priority
+    }
+}
+
+/// Reads the priorities of the tasks of an RTIC application from its source.
+///
+/// Both the name of the task function and the interrupt it is bound to are mapped to the
+/// priority, which defaults to 1 like in RTIC.
+pub fn app_priorities(source: &str) -> HashMap<String, u8> {
+    let mut priorities = HashMap::new();
+    let mut rest = source;
+    while let Some(index) = rest.find("#[task") {
+        rest = &rest[index + "#[task".len()..];
+        let args = if let Some(args) = rest.strip_prefix('(') {
+            match args.find(")]") {
+                Some(end) => {
+                    rest = &args[end + 2..];
+                    &args[..end]
+                }
+                None => break,
+            }
+        } else if let Some(after) = rest.strip_prefix(']') {
+            rest = after;
+            ""
+        } else {
+            continue;
+        };
+
+        let arg = |key: &str| {
+            args.split(',')
+                .filter_map(|arg| arg.split_once('='))
+                .find(|(name, _)| name.trim() == key)
+                .map(|(_, value)| value.trim())
+        };
+        let priority = match arg("priority").map(str::parse) {
+            Some(Ok(priority)) => priority,
+            Some(Err(_)) => continue,
+            None => 1,
+        };
+
+        // The function follows the attribute, possibly after other attributes.
+        let function = rest.find("fn ").map(|index| {
+            let name = rest[index + "fn ".len()..].trim_start();
+            let end = name
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(name.len());
+            &name[..end]
+        });
+        for name in [function, arg("binds")].into_iter().flatten() {
+            if !name.is_empty() {
+                priorities.insert(name.to_string(), priority);
+            }
+        }
+    }
+    priorities
+}
+
+/// Looks up the priority of a task by its full name, or by the last segment of its path, e.g.
+/// `uart0` for `app::uart0`.
+pub fn priority(priorities: &HashMap<String, u8>, task: &str) -> Option<u8> {
+    priorities
+        .get(task)
+        .or_else(|| priorities.get(task.rsplit("::").next()?))
+        .copied()
+}
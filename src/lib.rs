@@ -0,0 +1,17 @@
+//! Trace processing which does not depend on the user interface.
+
+pub mod analyze;
+pub mod axis;
+pub mod chain;
+pub mod chrome;
+pub mod compare;
+pub mod csv;
+pub mod ingest;
+pub mod layout;
+pub mod retention;
+pub mod rules;
+pub mod summary;
+pub mod time;
+pub mod trace;
+pub mod vcd;
+pub mod xml;
@@ -1,10 +1,20 @@
 use iced::{window, Application, Settings};
 use timeline::Timeline;
 
+mod cli;
+mod config;
 mod event_stream;
+mod export;
+mod session;
+mod style;
 mod timeline;
 
-pub fn main() -> iced::Result {
+pub fn main() -> anyhow::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     Timeline::run(Settings {
         antialiasing: true,
         window: window::Settings {
@@ -12,5 +22,6 @@ pub fn main() -> iced::Result {
             ..window::Settings::default()
         },
         ..Settings::default()
-    })
+    })?;
+    Ok(())
 }
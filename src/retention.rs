@@ -0,0 +1,140 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Limits on how much of the trace is kept in memory.
+///
+/// Whenever a limit is exceeded by more than a tenth, the oldest bars are evicted until the
+/// trace is within all limits again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Only keep bars which ended at most this many seconds before the latest event.
+    pub seconds: Option<f64>,
+    /// Only keep this many of the most recent bars.
+    pub bars: Option<usize>,
+    /// Only keep as many of the most recent bars as fit into about this many MiB.
+    pub memory_mib: Option<usize>,
+    /// Write evicted bars to a temporary file so they can still be browsed.
+    pub spill: bool,
+}
+
+impl Retention {
+    pub fn max_age_ns(&self) -> Option<usize> {
+        self.seconds.map(|seconds| (seconds * 1e9) as usize)
+    }
+
+    /// Returns the maximum number of bars to keep, given the estimated memory used per bar.
+    pub fn max_bars(&self, bar_size: usize) -> Option<usize> {
+        let by_memory = self
+            .memory_mib
+            .map(|memory_mib| memory_mib * 1024 * 1024 / bar_size.max(1));
+        match (self.bars, by_memory) {
+            (Some(bars), Some(by_memory)) => Some(bars.min(by_memory)),
+            (bars, by_memory) => bars.or(by_memory),
+        }
+        .map(|bars| bars.max(1))
+    }
+}
+
+/// A bar which was evicted from memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpilledBar {
+    pub start: usize,
+    pub end: usize,
+    pub channel: usize,
+}
+
+/// A group of bars which were evicted together.
+struct Chunk {
+    offset: u64,
+    len: u64,
+    /// The range covered by all bars of the chunk.
+    range: Range<usize>,
+}
+
+/// A disk-backed store for evicted bars.
+///
+/// Bars are appended in chunks, one per eviction, and reading a range only reads the chunks which
+/// overlap it.
+pub struct Spill {
+    file: File,
+    chunks: Vec<Chunk>,
+    len: u64,
+}
+
+impl Spill {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            file: tempfile::tempfile()?,
+            chunks: vec![],
+            len: 0,
+        })
+    }
+
+    pub fn write(&mut self, bars: &[SpilledBar]) -> io::Result<()> {
+        let range = match (
+            bars.iter().map(|bar| bar.start).min(),
+            bars.iter().map(|bar| bar.end).max(),
+        ) {
+            (Some(start), Some(end)) => start..end,
+            _ => return Ok(()),
+        };
+
+        self.file.seek(SeekFrom::Start(self.len))?;
+        let mut writer = BufWriter::new(&mut self.file);
+        let mut len = 0;
+        for bar in bars {
+            let line = format!("{} {} {}\n", bar.start, bar.end, bar.channel);
+            writer.write_all(line.as_bytes())?;
+            len += line.len() as u64;
+        }
+        writer.flush()?;
+
+        self.chunks.push(Chunk {
+            offset: self.len,
+            len,
+            range,
+        });
+        self.len += len;
+        Ok(())
+    }
+
+    /// Reads all bars which intersect the given range.
+    pub fn read(&mut self, range: Range<usize>) -> io::Result<Vec<SpilledBar>> {
+        let mut bars = vec![];
+        for chunk in &self.chunks {
+            if chunk.range.end < range.start || range.end <= chunk.range.start {
+                continue;
+            }
+
+            self.file.seek(SeekFrom::Start(chunk.offset))?;
+            let reader = BufReader::new((&mut self.file).take(chunk.len));
+            for line in reader.lines() {
+                let line = line?;
+                let mut fields = line.split(' ').map(str::parse::<usize>);
+                let bar = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(Ok(start)), Some(Ok(end)), Some(Ok(channel))) => SpilledBar {
+                        start,
+                        end,
+                        channel,
+                    },
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Invalid spilled bar {:?}", line),
+                        ))
+                    }
+                };
+                if bar.start < range.end && range.start <= bar.end {
+                    bars.push(bar);
+                }
+            }
+        }
+        Ok(bars)
+    }
+}
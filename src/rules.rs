@@ -0,0 +1,400 @@
+//! Timing assertions which are checked against a trace, e.g. to fail a CI pipeline on
+//! regressions.
+//!
+//! A rules file has one rule per line. Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! task app::foo max_duration < 50us
+//! task app::foo mean duration <= 20us
+//! task app::tick period 1ms +-5%
+//! task app::reply follows app::parse within 10us
+//! no overflows
+//! ```
+//!
+//! Durations can be compared to a limit with `<`, `<=`, `>` and `>=`. A period rule requires
+//! the time between the starts of all consecutive activations to be within the tolerance, which
+//! is a finite, non-negative percentage. A follows rule requires every activation of the second
+//! task to be followed by an activation of the first one, which starts after the second one
+//! started and at most the given time after it ended. The first task may preempt the second one.
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{
+    time::{self, TimeFormat},
+    trace::{Bar, TraceModel},
+    xml::escape,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Statistic {
+    Min,
+    Mean,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, limit: f64) -> bool {
+        match self {
+            Comparison::Less => value < limit,
+            Comparison::LessOrEqual => value <= limit,
+            Comparison::Greater => value > limit,
+            Comparison::GreaterOrEqual => value >= limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Assertion {
+    Duration {
+        task: String,
+        statistic: Statistic,
+        comparison: Comparison,
+        limit_ns: u64,
+    },
+    Period {
+        task: String,
+        period_ns: u64,
+        tolerance_percent: f64,
+    },
+    Follows {
+        task: String,
+        cause: String,
+        within_ns: u64,
+    },
+    NoOverflows,
+}
+
+/// An assertion together with the line it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// The line number, counted from one.
+    pub line: usize,
+    pub text: String,
+    pub assertion: Assertion,
+}
+
+/// The result of checking a rule, which passed if there are no failures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub rule: Rule,
+    pub failures: Vec<String>,
+}
+
+impl Outcome {
+    pub fn is_pass(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// An invalid line of a rules file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid rule on line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses all rules of a rules file.
+pub fn parse(rules: &str) -> Result<Vec<Rule>, ParseError> {
+    rules
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let assertion = parse_assertion(line).map_err(|message| ParseError {
+                line: number,
+                message,
+            })?;
+            Ok(Rule {
+                line: number,
+                text: line.to_string(),
+                assertion,
+            })
+        })
+        .collect()
+}
+
+fn parse_assertion(line: &str) -> Result<Assertion, String> {
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+    let task = match tokens[..] {
+        ["no", "overflows"] => return Ok(Assertion::NoOverflows),
+        ["task", task, ..] => task.to_string(),
+        _ => return Err(String::from("expected `task <name> ...` or `no overflows`")),
+    };
+    let duration = |tokens: &[&str]| {
+        let text = tokens.concat();
+        time::parse(&text).ok_or_else(|| format!("invalid time {:?}", text))
+    };
+
+    match tokens[2..] {
+        ["period", period, ref tolerance @ ..] => {
+            let tolerance = tolerance.concat();
+            let tolerance_percent = match tolerance.as_str() {
+                "" => 0.0,
+                _ => tolerance
+                    .strip_prefix('±')
+                    .or_else(|| tolerance.strip_prefix("+-"))
+                    .and_then(|tolerance| tolerance.strip_suffix('%'))
+                    .and_then(|tolerance| tolerance.parse::<f64>().ok())
+                    .filter(|tolerance| tolerance.is_finite() && *tolerance >= 0.0)
+                    .ok_or_else(|| {
+                        format!("invalid tolerance {:?}, expected e.g. +-5%", tolerance)
+                    })?,
+            };
+            Ok(Assertion::Period {
+                task,
+                period_ns: duration(&[period])?,
+                tolerance_percent,
+            })
+        }
+        ["always", "follows", cause, "within", ref within @ ..]
+        | ["follows", cause, "within", ref within @ ..] => Ok(Assertion::Follows {
+            task,
+            cause: cause.to_string(),
+            within_ns: duration(within)?,
+        }),
+        [statistic, "duration", comparison, ref limit @ ..]
+        | [statistic, comparison, ref limit @ ..] => {
+            let statistic = match statistic {
+                "min" | "min_duration" => Statistic::Min,
+                "mean" | "mean_duration" => Statistic::Mean,
+                "max" | "max_duration" => Statistic::Max,
+                _ => return Err(format!("unknown property {:?}", statistic)),
+            };
+            let comparison = match comparison {
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                ">=" => Comparison::GreaterOrEqual,
+                _ => return Err(format!("unknown comparison {:?}", comparison)),
+            };
+            Ok(Assertion::Duration {
+                task,
+                statistic,
+                comparison,
+                limit_ns: duration(limit)?,
+            })
+        }
+        _ => Err(String::from(
+            "expected a duration, period or follows rule after the task",
+        )),
+    }
+}
+
+/// Checks all rules against the trace.
+pub fn check(
+    rules: &[Rule],
+    model: &mut TraceModel,
+    time_format: &TimeFormat,
+) -> io::Result<Vec<Outcome>> {
+    let bars = model.all_bars()?;
+    let format = |ns: f64| time_format.format(ns.max(0.0).round() as u64);
+    // Returns the bars of the task in the order of their start, or a failure.
+    let task_bars = |task: &str| match model.channel(task) {
+        Some(channel) => Ok(bars
+            .iter()
+            .filter(|bar| bar.channel == channel)
+            .collect::<Vec<&Bar>>()),
+        None => Err(vec![format!(
+            "The task {} does not appear in the trace",
+            task
+        )]),
+    };
+
+    let outcomes = rules.iter().map(|rule| {
+        let failures = match &rule.assertion {
+            Assertion::NoOverflows => match model.overflow_timestamps() {
+                [] => vec![],
+                overflows => vec![format!(
+                    "{} overflows, the first at {}",
+                    overflows.len(),
+                    format(overflows[0] as f64)
+                )],
+            },
+            Assertion::Duration {
+                task,
+                statistic,
+                comparison,
+                limit_ns,
+            } => task_bars(task)
+                .and_then(|bars| {
+                    let durations = bars.iter().map(|bar| bar.duration() as f64);
+                    let value = match statistic {
+                        Statistic::Min => durations.reduce(f64::min),
+                        Statistic::Max => durations.reduce(f64::max),
+                        Statistic::Mean => {
+                            let count = durations.len() as f64;
+                            durations.reduce(|a, b| a + b).map(|sum| sum / count)
+                        }
+                    }
+                    .ok_or_else(|| {
+                        vec![format!("The task {} has no completed activations", task)]
+                    })?;
+                    if comparison.holds(value, *limit_ns as f64) {
+                        Ok(vec![])
+                    } else {
+                        Ok(vec![format!(
+                            "The {} duration is {}",
+                            match statistic {
+                                Statistic::Min => "min",
+                                Statistic::Mean => "mean",
+                                Statistic::Max => "max",
+                            },
+                            format(value)
+                        )])
+                    }
+                })
+                .unwrap_or_else(|failures| failures),
+            Assertion::Period {
+                task,
+                period_ns,
+                tolerance_percent,
+            } => task_bars(task)
+                .map(|bars| {
+                    if bars.len() < 2 {
+                        return vec![format!(
+                            "The task {} has fewer than two completed activations",
+                            task
+                        )];
+                    }
+                    let tolerance = *period_ns as f64 * tolerance_percent / 100.0;
+                    let (min, max) = (*period_ns as f64 - tolerance, *period_ns as f64 + tolerance);
+                    let periods = bars.windows(2).map(|pair| (pair[0].start, pair[1].start));
+                    let mut outliers =
+                        periods.filter(|&(a, b)| !(min..=max).contains(&((b - a) as f64)));
+                    match outliers.next() {
+                        None => vec![],
+                        Some((a, b)) => vec![format!(
+                            "{} of {} periods are outside {} - {}, e.g. {} at {}",
+                            outliers.count() + 1,
+                            bars.len() - 1,
+                            format(min),
+                            format(max),
+                            format((b - a) as f64),
+                            format(b as f64)
+                        )],
+                    }
+                })
+                .unwrap_or_else(|failures| failures),
+            Assertion::Follows {
+                task,
+                cause,
+                within_ns,
+            } => task_bars(task)
+                .and_then(|effects| Ok((effects, task_bars(cause)?)))
+                .map(|(effects, causes)| {
+                    let starts = effects.iter().map(|bar| bar.start).collect::<Vec<_>>();
+                    let mut missing = causes.iter().filter(|cause| {
+                        let index = starts.partition_point(|&start| start < cause.start);
+                        starts.get(index).map_or(true, |&start| {
+                            start.saturating_sub(cause.end) as u64 > *within_ns
+                        })
+                    });
+                    match missing.next() {
+                        None => vec![],
+                        Some(first) => vec![format!(
+                            "{} of {} activations of {} are not followed by {} within {}, \
+                             the first ending at {}",
+                            missing.count() + 1,
+                            causes.len(),
+                            cause,
+                            task,
+                            format(*within_ns as f64),
+                            format(first.end as f64)
+                        )],
+                    }
+                })
+                .unwrap_or_else(|failures| failures),
+        };
+        Outcome {
+            rule: rule.clone(),
+            failures,
+        }
+    });
+    Ok(outcomes.collect())
+}
+
+/// Writes one line per rule with its result, followed by its failures.
+pub fn write_text(outcomes: &[Outcome], mut out: impl Write) -> io::Result<()> {
+    for outcome in outcomes {
+        let result = if outcome.is_pass() { "PASS" } else { "FAIL" };
+        writeln!(
+            out,
+            "{}  line {}: {}",
+            result, outcome.rule.line, outcome.rule.text
+        )?;
+        for failure in &outcome.failures {
+            writeln!(out, "      {}", failure)?;
+        }
+    }
+    let passed = outcomes.iter().filter(|outcome| outcome.is_pass()).count();
+    writeln!(out, "{} of {} rules passed", passed, outcomes.len())?;
+    out.flush()
+}
+
+/// Writes the outcomes as a JUnit XML report with one test case per rule, which CI servers can
+/// show.
+pub fn write_junit(outcomes: &[Outcome], mut out: impl Write) -> io::Result<()> {
+    let failures = outcomes.iter().filter(|outcome| !outcome.is_pass()).count();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites tests="{}" failures="{}">"#,
+        outcomes.len(),
+        failures
+    )?;
+    writeln!(
+        out,
+        r#"  <testsuite name="racer" tests="{}" failures="{}">"#,
+        outcomes.len(),
+        failures
+    )?;
+    for outcome in outcomes {
+        let name = escape(&format!(
+            "line {}: {}",
+            outcome.rule.line, outcome.rule.text
+        ));
+        if outcome.is_pass() {
+            writeln!(
+                out,
+                r#"    <testcase name="{}" classname="racer.rules"/>"#,
+                name
+            )?;
+        } else {
+            let message = escape(&outcome.failures.join("\n"));
+            writeln!(
+                out,
+                r#"    <testcase name="{}" classname="racer.rules">"#,
+                name
+            )?;
+            writeln!(
+                out,
+                r#"      <failure message="{}">{}</failure>"#,
+                message, message
+            )?;
+            writeln!(out, "    </testcase>")?;
+        }
+    }
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")?;
+    out.flush()
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use rtic_scope_api::EventType;
+use rtic_scope_frontend_racer::{layout::LaneOrder, trace::TraceModel};
+use serde::{Deserialize, Serialize};
+
+/// A trace together with the state of the timeline, which is saved to a single JSON file and
+/// reopened exactly as it was.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// The version of the file format, which is increased on incompatible changes.
+    pub version: u32,
+    pub view: View,
+    /// Color overrides for tasks, as a map of task name to a `#rrggbb` color.
+    pub colors: HashMap<String, String>,
+    /// The priorities of the tasks which have one, by name.
+    pub priorities: HashMap<String, u8>,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+    /// The events which rebuild the trace, see [`TraceModel::events`].
+    pub events: Vec<(usize, EventType)>,
+}
+
+/// The state of the timeline.
+///
+/// Lanes cannot be hidden in the timeline, so which lanes are shown follows from the trace, the
+/// lane order and the scroll position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct View {
+    /// The scale of the timeline in px per ns.
+    pub zoom: f64,
+    /// The negated timestamp at the left edge of the timeline in ns.
+    pub pan: f64,
+    pub scroll: f32,
+    pub lane_height: f32,
+    pub is_grid_enabled: bool,
+    pub is_lane_info_enabled: bool,
+    #[serde(default)]
+    pub lane_order: LaneOrder,
+}
+
+/// A named bookmark at a point in time, or a free-text annotation over a range of the trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Marker {
+    pub start: u64,
+    /// Equal to `start` for bookmarks.
+    pub end: u64,
+    pub text: String,
+}
+
+impl Marker {
+    pub fn is_bookmark(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl Session {
+    pub const VERSION: u32 = 1;
+
+    /// Captures the trace of the model together with the given state of the timeline.
+    ///
+    /// Fails if bars were evicted from memory without being spilled to disk, as the session would
+    /// silently miss them.
+    pub fn capture(
+        model: &mut TraceModel,
+        view: View,
+        colors: HashMap<String, String>,
+        markers: Vec<Marker>,
+    ) -> anyhow::Result<Self> {
+        if model.evicted_before() > 0 && !model.is_spilling() {
+            bail!(
+                "The activations before {}ns were evicted from memory and not spilled to disk, \
+                 so they cannot be saved. Set `retention.spill` in the configuration to save them.",
+                model.evicted_before()
+            );
+        }
+        let priorities = model
+            .lanes()
+            .iter()
+            .filter_map(|lane| Some((lane.name.clone(), lane.priority?)))
+            .collect();
+        Ok(Self {
+            version: Self::VERSION,
+            view,
+            colors,
+            priorities,
+            markers,
+            events: model.events()?,
+        })
+    }
+
+    /// Adds the trace of the session to the model, which should be empty.
+    pub fn restore(&self, model: &mut TraceModel) {
+        for (timestamp, event) in &self.events {
+            model.add_event(*timestamp, event.clone());
+        }
+        for (name, &priority) in &self.priorities {
+            if let Some(channel) = model.channel(name) {
+                model.set_priority(channel, Some(priority));
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let session: Self = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if session.version > Self::VERSION {
+            bail!(
+                "The session {} was saved by a newer version of racer",
+                path.display()
+            );
+        }
+        Ok(session)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self)?;
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+use iced::{button, container, text_input, Color, Vector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Light, Theme::Dark];
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                background: Color::WHITE,
+                text: Color::BLACK,
+                dimmed_text: Color::from_rgb8(0x60, 0x60, 0x60),
+                grid: Color::BLACK,
+                gutter: Color::WHITE,
+                bar_text: Color::BLACK,
+                tooltip_background: Color::WHITE,
+                tooltip_text: Color::BLACK,
+                tooltip_border: Color::BLACK,
+                marker: Color::from_rgb8(0xD6, 0x28, 0x28),
+                chain: Color::from_rgb8(0x1F, 0x4E, 0xA8),
+                controls: Color::WHITE,
+                button: Color::from_rgb(0.87, 0.87, 0.87),
+                button_text: Color::BLACK,
+            },
+            Theme::Dark => Palette {
+                background: Color::from_rgb8(0x20, 0x22, 0x25),
+                text: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                dimmed_text: Color::from_rgb8(0xA0, 0xA0, 0xA0),
+                grid: Color::from_rgb8(0x55, 0x58, 0x5E),
+                gutter: Color::from_rgb8(0x2A, 0x2D, 0x31),
+                bar_text: Color::BLACK,
+                tooltip_background: Color::from_rgb(0.11, 0.42, 0.87),
+                tooltip_text: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                tooltip_border: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                marker: Color::from_rgb8(0xFF, 0x8C, 0x42),
+                chain: Color::from_rgb8(0x7F, 0xD4, 0xFF),
+                controls: Color::from_rgb8(0x2A, 0x2D, 0x31),
+                button: Color::from_rgb8(0x3C, 0x3F, 0x44),
+                button_text: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Light => write!(f, "Light"),
+            Theme::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+/// The colors used to draw the timeline and its controls.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub text: Color,
+    pub dimmed_text: Color,
+    pub grid: Color,
+    pub gutter: Color,
+    pub bar_text: Color,
+    pub tooltip_background: Color,
+    pub tooltip_text: Color,
+    pub tooltip_border: Color,
+    /// The color of bookmarks and annotations.
+    pub marker: Color,
+    /// The color of the arrows between the linked activations of a chain.
+    pub chain: Color,
+    pub controls: Color,
+    pub button: Color,
+    pub button_text: Color,
+}
+
+pub struct Controls(pub Theme);
+
+impl container::StyleSheet for Controls {
+    fn style(&self) -> container::Style {
+        let palette = self.0.palette();
+        container::Style {
+            text_color: Some(palette.text),
+            background: Some(palette.controls.into()),
+            ..container::Style::default()
+        }
+    }
+}
+
+pub struct Button(pub Theme);
+
+impl button::StyleSheet for Button {
+    fn active(&self) -> button::Style {
+        let palette = self.0.palette();
+        button::Style {
+            shadow_offset: Vector::new(0.0, 0.0),
+            background: Some(palette.button.into()),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color: palette.dimmed_text,
+            text_color: palette.button_text,
+        }
+    }
+}
+
+pub struct Tooltip(pub Theme);
+
+impl container::StyleSheet for Tooltip {
+    fn style(&self) -> container::Style {
+        let palette = self.0.palette();
+        container::Style {
+            text_color: Some(palette.tooltip_text),
+            background: Some(palette.tooltip_background.into()),
+            border_radius: 12.0,
+            ..container::Style::default()
+        }
+    }
+}
+
+pub struct TextInput(pub Theme);
+
+impl text_input::StyleSheet for TextInput {
+    fn active(&self) -> text_input::Style {
+        let palette = self.0.palette();
+        text_input::Style {
+            background: palette.background.into(),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color: palette.dimmed_text,
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_color: self.0.palette().text,
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        self.0.palette().dimmed_text
+    }
+
+    fn value_color(&self) -> Color {
+        self.0.palette().text
+    }
+
+    fn selection_color(&self) -> Color {
+        self.0.palette().tooltip_background
+    }
+}
@@ -0,0 +1,104 @@
+use std::ops::Range;
+
+/// A span of time in which a lane is mostly busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    pub start: u64,
+    pub end: u64,
+    /// The number of ns within the block which are actually covered by bars.
+    pub covered: u64,
+}
+
+impl Block {
+    /// Returns the fraction of the block which is covered by bars.
+    pub fn density(&self) -> f32 {
+        (self.covered as f64 / (self.end - self.start).max(1) as f64).min(1.0) as f32
+    }
+}
+
+/// The activity of a single lane at multiple resolutions.
+///
+/// At level `n` all bars which are less than [`Summary::bucket_ns`] apart are merged into a
+/// single [`Block`], so any level holds at most one block per bucket. When zoomed out, drawing
+/// the blocks of the level whose bucket is about one pixel wide bounds the amount of work per
+/// frame by the width of the screen instead of by the number of bars.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    levels: Vec<Vec<Block>>,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Summary {
+    /// The bucket size of the finest level in ns.
+    pub const BASE_BUCKET_NS: u64 = 1 << 10;
+    /// The number of levels, the coarsest having buckets of about 20 hours.
+    pub const LEVELS: usize = 37;
+    /// The finest level which is kept for evicted parts of the trace, with buckets of about 17ms.
+    pub const RETAINED_LEVEL: usize = 14;
+
+    pub fn new() -> Self {
+        Self {
+            levels: vec![vec![]; Self::LEVELS],
+        }
+    }
+
+    pub fn bucket_ns(level: usize) -> u64 {
+        Self::BASE_BUCKET_NS << level
+    }
+
+    /// Returns the coarsest level whose buckets are not larger than the given number of ns.
+    pub fn level_for(ns: u64) -> usize {
+        let mut level = 0;
+        while level + 1 < Self::LEVELS && Self::bucket_ns(level + 1) <= ns {
+            level += 1;
+        }
+        level
+    }
+
+    /// Adds a bar to all levels.
+    pub fn insert(&mut self, start: u64, end: u64) {
+        for (level, blocks) in self.levels.iter_mut().enumerate() {
+            let gap = Self::bucket_ns(level);
+
+            // All blocks from `first` up to `last` are closer than `gap` to the new bar and are
+            // merged with it. Bars mostly arrive in order, so this is usually the last block.
+            let first = blocks.partition_point(|b| b.end + gap < start);
+            let last = blocks.partition_point(|b| b.start <= end + gap);
+
+            let mut merged = Block {
+                start,
+                end,
+                covered: end - start,
+            };
+            for block in &blocks[first..last.max(first)] {
+                merged.start = merged.start.min(block.start);
+                merged.end = merged.end.max(block.end);
+                merged.covered += block.covered;
+            }
+            blocks.splice(first..last.max(first), std::iter::once(merged));
+        }
+    }
+
+    /// Returns the blocks of the given level which intersect the given range.
+    pub fn blocks(&self, level: usize, range: Range<u64>) -> &[Block] {
+        let blocks = &self.levels[level];
+        let first = blocks.partition_point(|b| b.end < range.start);
+        let last = blocks.partition_point(|b| b.start < range.end);
+        &blocks[first..last.max(first)]
+    }
+
+    /// Drops the blocks which end before the given timestamp from all levels finer than
+    /// [`Summary::RETAINED_LEVEL`], as their size grows with the number of bars instead of with
+    /// the length of the trace.
+    pub fn evict(&mut self, before: u64) {
+        for blocks in &mut self.levels[..Self::RETAINED_LEVEL] {
+            let evicted = blocks.partition_point(|b| b.end < before);
+            blocks.drain(..evicted);
+        }
+    }
+}
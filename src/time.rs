@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+/// How times of a minute and longer are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LongUnits {
+    /// Keep using SI prefixes, e.g. `1.5ks`.
+    Si,
+    /// Split into hours, minutes and seconds, e.g. `25min 00s`.
+    Clock,
+}
+
+/// Formats timestamps in ns with a unit which fits their magnitude.
+///
+/// All arithmetic is done on integers, so timestamps hours into a trace keep ns precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeFormat {
+    /// The number of significant digits shown by [`TimeFormat::format`].
+    pub significant_digits: u32,
+    pub long_units: LongUnits,
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        Self {
+            significant_digits: 3,
+            long_units: LongUnits::Clock,
+        }
+    }
+}
+
+const SI_UNITS: &[(u32, &str)] = &[
+    (0, "ns"),
+    (3, "us"),
+    (6, "ms"),
+    (9, "s"),
+    (12, "ks"),
+    (15, "Ms"),
+];
+
+const NS_PER_S: u64 = 1_000_000_000;
+const NS_PER_MIN: u64 = 60 * NS_PER_S;
+const NS_PER_H: u64 = 60 * NS_PER_MIN;
+
+impl TimeFormat {
+    /// Formats the timestamp rounded to the configured number of significant digits.
+    pub fn format(&self, ns: u64) -> String {
+        let significant_digits = self.significant_digits.max(1);
+        let mut dropped = decimal_digits(ns).saturating_sub(significant_digits);
+        // Rounding up may add a digit, e.g. 999.6us becomes 1.000ms instead of 1.00ms.
+        let rounded = round(ns, 10u64.pow(dropped));
+        dropped = decimal_digits(rounded).saturating_sub(significant_digits);
+        if self.long_units == LongUnits::Clock && rounded >= NS_PER_MIN {
+            // The seconds are always shown, regardless of the number of significant digits.
+            return format_clock(ns, 10u64.pow(dropped).min(NS_PER_S));
+        }
+        self.format_with_resolution(rounded, 10u64.pow(dropped))
+    }
+
+    /// Formats the timestamp with just enough decimals to tell apart timestamps which are the
+    /// given number of ns apart, e.g. the labels of grid lines with that spacing.
+    pub fn format_with_resolution(&self, ns: u64, resolution_ns: u64) -> String {
+        // Only use whole powers of ten, so the shown decimals are exact.
+        let step = 10u64.pow(decimal_digits(resolution_ns.max(1)) - 1);
+        if self.long_units == LongUnits::Clock && ns >= NS_PER_MIN {
+            return format_clock(ns, resolution_ns);
+        }
+
+        let ns = round(ns, step);
+        let mut exponent = 0;
+        for &(unit_exponent, _) in SI_UNITS {
+            if ns >= 10u64.pow(unit_exponent) {
+                exponent = unit_exponent;
+            }
+        }
+        if self.long_units == LongUnits::Clock && ns >= NS_PER_MIN {
+            // Rounding carried over into the next minute.
+            return format_clock(ns, resolution_ns);
+        }
+        let unit = SI_UNITS.iter().find(|(e, _)| *e == exponent).unwrap().1;
+
+        let decimals = exponent.saturating_sub(decimal_digits(step) - 1);
+        format!("{}{}", fixed(ns, exponent, decimals), unit)
+    }
+}
+
+/// Parses a duration like `50us`, `1.5ms` or `2 min` into ns, rounded to whole ns.
+///
+/// Accepts the SI units of [`TimeFormat`] as well as `µs`, `min` and `h`.
+pub fn parse(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (number, unit) = text.split_at(split);
+    let unit_ns: u128 = match unit.trim_start() {
+        "ns" => 1,
+        "us" | "µs" => 1_000,
+        "ms" => 1_000_000,
+        "s" => NS_PER_S as u128,
+        "ks" => 1_000 * NS_PER_S as u128,
+        "Ms" => 1_000_000 * NS_PER_S as u128,
+        "min" => NS_PER_MIN as u128,
+        "h" => NS_PER_H as u128,
+        _ => return None,
+    };
+
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if integer.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return None;
+    }
+    let parse_digits = |digits: &str| match digits {
+        "" => Some(0),
+        _ if digits.len() <= 30 => digits.parse::<u128>().ok(),
+        _ => None,
+    };
+    // Digits beyond the one after the resolution of 1ns cannot change the rounded result.
+    let fraction = &fraction[..fraction.len().min(unit_ns.to_string().len())];
+    let scale = 10u128.checked_pow(fraction.len() as u32)?;
+    let fraction_ns = parse_digits(fraction)?.checked_mul(unit_ns)?;
+    let ns = parse_digits(integer)?
+        .checked_mul(unit_ns)?
+        .checked_add(fraction_ns.checked_add(scale / 2)? / scale)?;
+    ns.try_into().ok()
+}
+
+/// Returns the number of decimal digits of the number, which is 1 for 0.
+fn decimal_digits(n: u64) -> u32 {
+    let mut digits = 1;
+    let mut n = n / 10;
+    while n > 0 {
+        digits += 1;
+        n /= 10;
+    }
+    digits
+}
+
+/// Rounds to the nearest multiple of `step`, with halves rounded up.
+fn round(n: u64, step: u64) -> u64 {
+    let remainder = n % step;
+    if remainder >= step - step / 2 {
+        (n - remainder).saturating_add(step)
+    } else {
+        n - remainder
+    }
+}
+
+/// Formats `n / 10^exponent` with the given number of decimals. `n` must not have any non-zero
+/// digits after the last shown one.
+fn fixed(n: u64, exponent: u32, decimals: u32) -> String {
+    let scale = 10u64.pow(exponent);
+    let integer = n / scale;
+    if decimals == 0 {
+        return integer.to_string();
+    }
+    let fraction = (n % scale) / 10u64.pow(exponent - decimals);
+    format!(
+        "{}.{:0width$}",
+        integer,
+        fraction,
+        width = decimals as usize
+    )
+}
+
+fn format_clock(ns: u64, resolution_ns: u64) -> String {
+    // Whole minutes or hours are enough if the resolution is that coarse.
+    let step = if resolution_ns >= NS_PER_H {
+        NS_PER_H
+    } else if resolution_ns >= NS_PER_MIN {
+        NS_PER_MIN
+    } else {
+        10u64
+            .pow(decimal_digits(resolution_ns.max(1)) - 1)
+            .min(NS_PER_S)
+    };
+    let ns = round(ns, step);
+
+    let hours = ns / NS_PER_H;
+    let minutes = ns % NS_PER_H / NS_PER_MIN;
+    let seconds = ns % NS_PER_MIN;
+
+    let mut parts = vec![];
+    if hours > 0 || step == NS_PER_H {
+        parts.push(format!("{}h", hours));
+    }
+    if step < NS_PER_H {
+        if hours > 0 {
+            parts.push(format!("{:02}min", minutes));
+        } else {
+            parts.push(format!("{}min", minutes));
+        }
+    }
+    if step < NS_PER_MIN {
+        let decimals = 9 - (decimal_digits(step) - 1);
+        let seconds = fixed(seconds, 9, decimals);
+        // Pad the integer part of the seconds to two digits.
+        let padding = if seconds.find('.').unwrap_or(seconds.len()) < 2 {
+            "0"
+        } else {
+            ""
+        };
+        parts.push(format!("{}{}s", padding, seconds));
+    }
+    parts.join(" ")
+}
@@ -1,24 +1,321 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use comparison::{AlignOn, Comparison};
 use grid::Grid;
 use iced::{
     button::{self, Button},
-    executor, Alignment, Application, Checkbox, Color, Column, Command, Container, Element, Length,
-    Point, Row, Subscription, Text,
+    executor, pick_list, text_input, time, tooltip, Alignment, Application, Checkbox, Color,
+    Column, Command, Container, Element, Length, PickList, Point, Row, Subscription, Text,
+    TextInput, Tooltip,
+};
+use minimap::Minimap;
+use sidebar::Sidebar;
+
+use rtic_scope_frontend_racer::{
+    chain::{Chain, Latency},
+    compare::{self, Alignment},
+    layout::LaneOrder,
+    time::TimeFormat,
 };
 
-use crate::event_stream::Progress;
+use crate::{
+    cli,
+    config::{self, Config},
+    event_stream::Progress,
+    export,
+    session::Session,
+    style::{self, Theme},
+};
 
 #[derive(Default)]
 pub struct Timeline {
     grid: Grid,
+    minimap: Minimap,
+    /// Whether the trace changed since the minimap was last updated.
+    is_minimap_dirty: bool,
+    sidebar: Sidebar,
+    comparison: Option<Comparison>,
     controls: Controls,
+    config: Config,
+    export: Export,
+    session: SessionFile,
+    compare: CompareSettings,
+    chain: ChainSettings,
+    app: AppDescription,
+}
+
+/// The settings of the image export.
+struct Export {
+    path: String,
+    /// The width of the timeline in px, as entered.
+    width: String,
+    is_full_trace: bool,
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Self {
+            path: String::from("racer.svg"),
+            width: String::from("1920"),
+            is_full_trace: false,
+        }
+    }
+}
+
+/// The path sessions are saved to and opened from.
+struct SessionFile {
+    path: String,
+}
+
+impl Default for SessionFile {
+    fn default() -> Self {
+        Self {
+            path: String::from("racer-session.json"),
+        }
+    }
+}
+
+/// The settings of the comparison of two traces, as entered.
+#[derive(Default)]
+struct CompareSettings {
+    /// The path of the first trace, or empty to compare with the current trace.
+    before: String,
+    after: String,
+    align_on: AlignOn,
+    task: String,
+    /// The number of the activation to align on, counted from one.
+    activation: String,
+    /// The manual offset in ns.
+    offset: String,
+}
+
+/// The path of the application description the task priorities are read from, as entered.
+#[derive(Default)]
+struct AppDescription {
+    path: String,
+}
+
+/// The chain of tasks whose instances are linked in the timeline.
+#[derive(Default)]
+struct ChainSettings {
+    /// The tasks as entered, e.g. `UART0 -> parse -> send_reply`.
+    tasks: String,
+    /// The end-to-end latency of the shown chain.
+    latency: Option<Latency>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ToggleGrid(bool),
+    ToggleLaneInfo(bool),
+    ThemeSelected(Theme),
+    ExportPathChanged(String),
+    ExportWidthChanged(String),
+    ToggleExportFullTrace(bool),
+    Export,
+    SessionPathChanged(String),
+    SaveSession,
+    OpenSession,
+    CompareBeforeChanged(String),
+    CompareAfterChanged(String),
+    AlignOnSelected(AlignOn),
+    AlignTaskChanged(String),
+    AlignActivationChanged(String),
+    AlignOffsetChanged(String),
+    Compare,
+    CloseComparison,
+    Comparison(grid::Message),
+    ChainChanged(String),
+    ShowChain,
+    ClearChain,
+    LaneOrderSelected(LaneOrder),
+    AppPathChanged(String),
+    LoadPriorities,
     Progress(Progress),
+    Grid(grid::Message),
+    Minimap(minimap::Message),
+    Sidebar(sidebar::Message),
+    Tick,
     Reset,
-    None,
+}
+
+impl Timeline {
+    fn apply_config(&mut self, config: Config) {
+        self.set_colors(&config.colors);
+        self.grid.set_theme(config.theme);
+        self.grid.set_retention(config.retention.clone());
+        self.grid.set_time_format(config.time_format);
+        self.grid.set_lane_order(config.lane_order);
+        self.minimap.set_theme(config.theme);
+        if let Some(app) = &config.app {
+            self.app.path = app.display().to_string();
+            if let Err(e) = self.load_priorities() {
+                self.grid.set_status(format!("Error {:?}", e));
+            }
+        }
+        self.config = config;
+    }
+
+    /// Reads the task priorities from the application description and rearranges the lanes,
+    /// returning how many tasks of the trace have one.
+    fn load_priorities(&mut self) -> anyhow::Result<usize> {
+        let priorities = config::load_priorities(Path::new(&self.app.path))?;
+        if let Some(comparison) = &mut self.comparison {
+            comparison.grid.set_priorities(priorities.clone());
+        }
+        Ok(self.grid.set_priorities(priorities))
+    }
+
+    /// Overrides the colors of tasks with the given `#rrggbb` colors by task name.
+    fn set_colors(&mut self, colors: &HashMap<String, String>) {
+        let mut overrides = HashMap::new();
+        for (task, color) in colors {
+            match parse_color(color) {
+                Some(color) => {
+                    overrides.insert(task.clone(), color);
+                }
+                None => self
+                    .grid
+                    .set_status(format!("Invalid color {:?} for task {}", color, task)),
+            }
+        }
+        self.grid.set_color_overrides(overrides);
+    }
+
+    /// Exports an image of the timeline or the trace data, depending on the extension of the
+    /// export path.
+    fn export(&mut self) {
+        let path = Path::new(&self.export.path);
+        let result = if export::is_image(path) {
+            match self.export.width.parse::<f32>() {
+                Ok(width) if width >= 1.0 => self
+                    .grid
+                    .export_scene(self.export.is_full_trace, width)
+                    .save(path),
+                _ => Err(anyhow!("Invalid export width {:?}", self.export.width)),
+            }
+        } else {
+            export::save_trace(self.grid.model_mut(), path)
+        };
+        match result {
+            Ok(()) => self
+                .grid
+                .set_status(format!("Exported to {}.", path.display())),
+            Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+        }
+    }
+
+    fn save_session(&mut self) -> anyhow::Result<()> {
+        let colors = self
+            .grid
+            .color_overrides()
+            .iter()
+            .map(|(task, &color)| (task.clone(), format_color(color)))
+            .collect();
+        let view = self.grid.view_state();
+        let markers = self.grid.markers().to_vec();
+        Session::capture(self.grid.model_mut(), view, colors, markers)?
+            .save(Path::new(&self.session.path))
+    }
+
+    fn open_session(&mut self) -> anyhow::Result<()> {
+        let session = Session::load(Path::new(&self.session.path))?;
+        self.set_colors(&session.colors);
+        self.grid.open_session(&session);
+        self.chain.latency = None;
+        self.update_minimap();
+        Ok(())
+    }
+
+    /// Reads the traces to compare and shows the second one below the first.
+    fn compare(&mut self) -> anyhow::Result<()> {
+        let settings = &self.compare;
+        let alignment = match settings.align_on {
+            AlignOn::Start => Alignment::Start,
+            AlignOn::Activation => {
+                let number = settings
+                    .activation
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&number| number >= 1)
+                    .with_context(|| format!("Invalid activation {:?}", settings.activation))?;
+                Alignment::Activation {
+                    task: settings.task.clone(),
+                    index: number - 1,
+                }
+            }
+            AlignOn::Manual => Alignment::Manual(
+                settings
+                    .offset
+                    .parse()
+                    .with_context(|| format!("Invalid offset {:?}", settings.offset))?,
+            ),
+        };
+
+        let mut after = cli::read_trace(&settings.after)?;
+        let path = settings.after.clone();
+        if !settings.before.is_empty() {
+            let before = cli::read_trace(&settings.before)?;
+            self.grid.load(before);
+            self.update_minimap();
+        }
+
+        let offset = alignment
+            .offset(self.grid.model_mut(), &mut after)?
+            .context("Both traces need the event to align on")?;
+        let deltas = compare::stats_delta(self.grid.model(), &after);
+
+        let mut grid = Grid::new();
+        grid.set_color_overrides(self.grid.color_overrides().clone());
+        grid.set_theme(self.config.theme);
+        grid.set_time_format(self.config.time_format);
+        grid.toggle_grid(self.grid.are_lines_visible());
+        grid.toggle_lane_info(self.grid.is_lane_info_visible());
+        grid.set_lane_order(self.grid.lane_order());
+        grid.set_priorities(self.grid.priorities().clone());
+        grid.load(after);
+        self.comparison = Some(Comparison::new(grid, path, offset, deltas));
+        self.sync_comparison();
+        Ok(())
+    }
+
+    /// Links the instances of the entered chain in the timeline and computes their latency.
+    fn show_chain(&mut self) -> anyhow::Result<()> {
+        let chain = self.chain.tasks.parse::<Chain>().map_err(|e| anyhow!(e))?;
+        let instances = chain.instances(self.grid.model_mut())?;
+        self.chain.latency = Some(Latency::new(&instances));
+        self.grid.set_chain(instances);
+        Ok(())
+    }
+
+    /// Gives the compared trace the zoom and pan of the current one, shifted by the offset.
+    fn sync_comparison(&mut self) {
+        if let Some(comparison) = &mut self.comparison {
+            let (zoom, pan) = self.grid.transform();
+            comparison
+                .grid
+                .set_transform(zoom, pan + comparison.offset as f64);
+        }
+    }
+
+    fn update_minimap_view(&mut self) {
+        let (start, end) = self.grid.visible_range();
+        self.minimap.set_view(start, end);
+    }
+
+    fn update_minimap(&mut self) {
+        let (start, end) = self.grid.trace_range();
+        self.minimap.set_range(start, end);
+        self.minimap
+            .set_density(self.grid.density(Minimap::BINS), self.grid.lane_colors());
+        self.update_minimap_view();
+        self.is_minimap_dirty = false;
+    }
 }
 
 impl Application for Timeline {
@@ -27,7 +324,12 @@ impl Application for Timeline {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
-        (Self { ..Self::default() }, Command::none())
+        let mut timeline = Self { ..Self::default() };
+        match Config::load() {
+            Ok(config) => timeline.apply_config(config),
+            Err(e) => timeline.grid.set_status(format!("Error {:?}", e)),
+        }
+        (timeline, Command::none())
     }
 
     fn title(&self) -> String {
@@ -36,9 +338,139 @@ impl Application for Timeline {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::ToggleGrid(show_grid_lines) => self.grid.toggle_grid(show_grid_lines),
-            Message::Reset => self.grid.reset_state(),
-            Message::None => todo!(),
+            Message::ToggleGrid(show_grid_lines) => {
+                self.grid.toggle_grid(show_grid_lines);
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.grid.toggle_grid(show_grid_lines);
+                }
+            }
+            Message::ToggleLaneInfo(show_lane_info) => {
+                self.grid.toggle_lane_info(show_lane_info);
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.grid.toggle_lane_info(show_lane_info);
+                }
+            }
+            Message::ThemeSelected(theme) => {
+                self.grid.set_theme(theme);
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.grid.set_theme(theme);
+                }
+                self.minimap.set_theme(theme);
+                self.config.theme = theme;
+                if let Err(e) = self.config.save() {
+                    self.grid.set_status(format!("Error {:?}", e));
+                }
+            }
+            Message::ExportPathChanged(path) => self.export.path = path,
+            Message::ExportWidthChanged(width) => self.export.width = width,
+            Message::ToggleExportFullTrace(is_full_trace) => {
+                self.export.is_full_trace = is_full_trace
+            }
+            Message::Export => self.export(),
+            Message::SessionPathChanged(path) => self.session.path = path,
+            Message::SaveSession => match self.save_session() {
+                Ok(()) => self
+                    .grid
+                    .set_status(format!("Saved the session to {}.", self.session.path)),
+                Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+            },
+            Message::OpenSession => match self.open_session() {
+                Ok(()) => self
+                    .grid
+                    .set_status(format!("Opened the session {}.", self.session.path)),
+                Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+            },
+            Message::Reset => {
+                self.chain.latency = None;
+                self.grid.reset_state();
+                self.update_minimap();
+            }
+            Message::CompareBeforeChanged(path) => self.compare.before = path,
+            Message::CompareAfterChanged(path) => self.compare.after = path,
+            Message::AlignOnSelected(align_on) => self.compare.align_on = align_on,
+            Message::AlignTaskChanged(task) => self.compare.task = task,
+            Message::AlignActivationChanged(activation) => self.compare.activation = activation,
+            Message::AlignOffsetChanged(offset) => self.compare.offset = offset,
+            Message::Compare => match self.compare() {
+                Ok(()) => self.grid.set_status("Compared the traces."),
+                Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+            },
+            Message::CloseComparison => self.comparison = None,
+            Message::ChainChanged(tasks) => self.chain.tasks = tasks,
+            Message::ShowChain => match self.show_chain() {
+                Ok(()) => self.grid.set_status(format!(
+                    "Found {} instances of the chain.",
+                    self.chain
+                        .latency
+                        .as_ref()
+                        .map_or(0, |latency| latency.instances)
+                )),
+                Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+            },
+            Message::ClearChain => {
+                self.chain.latency = None;
+                self.grid.set_chain(vec![]);
+            }
+            Message::LaneOrderSelected(order) => {
+                self.grid.set_lane_order(order);
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.grid.set_lane_order(order);
+                }
+                self.config.lane_order = order;
+                if let Err(e) = self.config.save() {
+                    self.grid.set_status(format!("Error {:?}", e));
+                }
+            }
+            Message::AppPathChanged(path) => self.app.path = path,
+            Message::LoadPriorities => match self.load_priorities() {
+                Ok(count) => {
+                    self.grid.set_status(format!(
+                        "Read the priorities of {} tasks of the trace from {}.",
+                        count, self.app.path
+                    ));
+                    self.config.app = Some(PathBuf::from(&self.app.path));
+                    if let Err(e) = self.config.save() {
+                        self.grid.set_status(format!("Error {:?}", e));
+                    }
+                }
+                Err(e) => self.grid.set_status(format!("Error {:?}", e)),
+            },
+            Message::Grid(grid::Message::ViewChanged) => {
+                self.update_minimap_view();
+                self.sync_comparison();
+            }
+            Message::Grid(grid::Message::MarkersChanged) => {}
+            Message::Comparison(grid::Message::ViewChanged) => {
+                if let Some(comparison) = &self.comparison {
+                    let (zoom, pan) = comparison.grid.transform();
+                    self.grid
+                        .set_transform(zoom, pan - comparison.offset as f64);
+                    self.update_minimap_view();
+                }
+            }
+            Message::Comparison(grid::Message::MarkersChanged) => {}
+            Message::Minimap(minimap::Message::Navigate(ns)) => {
+                self.grid.center_on(ns);
+                self.update_minimap_view();
+                self.sync_comparison();
+            }
+            Message::Sidebar(message) => match message {
+                sidebar::Message::TextChanged(index, text) => {
+                    self.grid.set_marker_text(index, text)
+                }
+                sidebar::Message::GoTo(index) => {
+                    self.grid.go_to_marker(index);
+                    self.update_minimap_view();
+                    self.sync_comparison();
+                }
+                sidebar::Message::Delete(index) => self.grid.remove_marker(index),
+            },
+            Message::Tick => {
+                // Computing the density walks the whole trace, so it is only done periodically.
+                if self.is_minimap_dirty {
+                    self.update_minimap();
+                }
+            }
             Message::Progress(progress) => match progress {
                 Progress::Initialized => {
                     self.grid.set_status("Initialized. Waiting for connection.")
@@ -47,10 +479,10 @@ impl Application for Timeline {
                     self.grid.set_status(format!("Connected to {:?}.", address))
                 }
                 Progress::Event(events) => {
-                    for event in events.events {
-                        self.grid
-                            .add_event(events.timestamp.offset.as_nanos() as usize, event);
-                    }
+                    self.grid.ingest(&events);
+                    self.is_minimap_dirty = true;
+                    self.update_minimap_view();
+                    self.sync_comparison();
                 }
                 Progress::Error(error) => self.grid.set_status(format!("Error {:?}", error)),
                 Progress::None => {}
@@ -59,17 +491,62 @@ impl Application for Timeline {
         Command::none()
     }
 
+    fn background_color(&self) -> Color {
+        self.config.theme.palette().background
+    }
+
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::from_recipe(crate::event_stream::EventStream {}).map(Message::Progress)
+        Subscription::batch(vec![
+            Subscription::from_recipe(crate::event_stream::EventStream {}).map(Message::Progress),
+            time::every(Duration::from_millis(500)).map(|_| Message::Tick),
+        ])
     }
 
     fn view(&mut self) -> Element<Message> {
-        let controls = self
-            .controls
-            .view(true, self.grid.are_lines_visible(), self.grid.status());
+        let controls = self.controls.view(ControlsState {
+            is_playing: true,
+            is_grid_enabled: self.grid.are_lines_visible(),
+            is_lane_info_enabled: self.grid.is_lane_info_visible(),
+            theme: self.config.theme,
+            status: self.grid.status(),
+            export: &self.export,
+            session: &self.session,
+            compare: &self.compare,
+            is_comparing: self.comparison.is_some(),
+            chain: &self.chain,
+            time_format: self.config.time_format,
+            lane_order: self.grid.lane_order(),
+            app: &self.app,
+        });
+
+        let sidebar = self
+            .sidebar
+            .view(
+                self.grid.markers(),
+                self.config.theme,
+                self.config.time_format,
+            )
+            .map(Message::Sidebar);
+
+        let mut timelines = Column::new()
+            .height(Length::Fill)
+            .push(self.grid.view().map(Message::Grid));
+        if let Some(comparison) = &mut self.comparison {
+            timelines = timelines.push(
+                comparison
+                    .view(self.config.theme, self.config.time_format)
+                    .map(Message::Comparison),
+            );
+        }
 
         let content = Column::new()
-            .push(self.grid.view().map(move |_message| Message::None))
+            .push(self.minimap.view().map(Message::Minimap))
+            .push(
+                Row::new()
+                    .height(Length::Fill)
+                    .push(timelines)
+                    .push(sidebar),
+            )
             .push(controls);
 
         Container::new(content)
@@ -79,11 +556,17 @@ impl Application for Timeline {
     }
 }
 
-mod grid {
-    use crate::timeline::to_si_time;
+mod comparison;
+mod minimap;
+mod sidebar;
 
-    use super::{Bar, EventStyle, Interaction, Paint};
-    use bio::data_structures::interval_tree::IntervalTree;
+mod grid {
+    use super::{elide, task_color, text_width, EventStyle, Interaction, Paint};
+    use crate::{
+        export::{Anchor, Scene},
+        session::{Marker, Session, View},
+        style::{Palette, Theme},
+    };
     use iced::{
         alignment,
         canvas::{self, Cache, Canvas, Cursor, Frame, Geometry, Path, Text},
@@ -91,30 +574,65 @@ mod grid {
             event::{self, Event},
             Stroke,
         },
-        mouse, Color, Element, Font, Length, Point, Rectangle, Size,
+        keyboard, mouse, Color, Element, Font, Length, Point, Rectangle, Size, Vector,
+    };
+    use rtic_scope_api::EventChunk;
+    use rtic_scope_frontend_racer::{
+        axis::Axis,
+        chain::Instance,
+        layout::{self, LaneOrder, Layout, Row},
+        retention::Retention,
+        summary::Summary,
+        time::TimeFormat,
+        trace::{Bar, Lane, TraceModel},
     };
-    use itertools::Itertools;
-    use rtic_scope_api::EventType;
-    use std::collections::HashMap;
+    use std::{collections::HashMap, ops::Range};
 
     pub struct Grid {
         interaction: Interaction,
         bar_cache: Cache,
         grid_cache: Cache,
+        gutter_cache: Cache,
+        ruler_cache: Cache,
+        marker_cache: Cache,
+        chain_cache: Cache,
         is_grid_enabled: bool,
-        zoom: f32,
-        pan: f32,
-        bars: IntervalTree<usize, Bar>,
-        started_bars: Vec<Bar>,
-        channel_map: Vec<String>,
+        is_lane_info_enabled: bool,
+        /// The scale of the timeline in px per ns.
+        zoom: f64,
+        /// The negated timestamp at the left edge of the timeline in ns.
+        pan: f64,
+        scroll: f32,
+        lane_height: f32,
+        modifiers: keyboard::Modifiers,
+        model: TraceModel,
+        /// Evicted bars which were read back from the spill file for the visible range.
+        paged_bars: Vec<Bar>,
+        paged_range: Range<usize>,
+        /// The style of each lane, indexed by channel.
+        styles: Vec<EventStyle>,
+        color_overrides: HashMap<String, Color>,
+        layout: Layout,
+        /// The priorities of tasks from an application description, by name, which are applied
+        /// to the tasks as they appear.
+        priorities: HashMap<String, u8>,
+        /// Bookmarks and annotations, sorted by their start.
+        markers: Vec<Marker>,
+        /// The instances of a chain whose activations are linked by arrows, sorted by their start.
+        chain: Vec<Instance>,
+        palette: Palette,
+        time_format: TimeFormat,
         status: String,
-        min: usize,
-        max: usize,
         width: usize,
+        height: f32,
     }
 
     #[derive(Debug, Clone)]
-    pub enum Message {}
+    pub enum Message {
+        /// The visible range of the timeline changed.
+        ViewChanged,
+        MarkersChanged,
+    }
 
     impl Default for Grid {
         fn default() -> Self {
@@ -123,24 +641,56 @@ mod grid {
     }
 
     impl Grid {
-        const INITIAL_ZOOM: f32 = 0.0;
-        const INITIAL_PAN: f32 = 0.5;
+        const INITIAL_ZOOM: f64 = 0.0;
+        const INITIAL_PAN: f64 = 0.5;
+        const INITIAL_LANE_HEIGHT: f32 = 20.0;
+        const MIN_LANE_HEIGHT: f32 = 8.0;
+        const MAX_LANE_HEIGHT: f32 = 80.0;
+        const LANE_PADDING: f32 = 8.0;
+        const RULER_HEIGHT: f32 = 30.0;
+        const OFFSET_TOP: f32 = Self::RULER_HEIGHT + 8.0;
+        /// The minimum distance between two labelled ticks in px, which fits the longest labels.
+        const TICK_SPACING: f64 = 120.0;
+        const GUTTER_WIDTH: f32 = 160.0;
+        const SWATCH_WIDTH: f32 = 10.0;
+        /// About one px per 17 minutes.
+        const MIN_ZOOM: f64 = 1e-12;
+        /// The distance in px the cursor has to be dragged to create an annotation instead of a
+        /// bookmark.
+        const MIN_DRAG: f32 = 4.0;
+        /// The maximum width of the label of a bookmark in px.
+        const MARKER_LABEL_WIDTH: f32 = 200.0;
 
         pub fn new() -> Self {
             let mut s = Self {
                 interaction: Interaction::None,
                 bar_cache: Cache::default(),
                 grid_cache: Cache::default(),
+                gutter_cache: Cache::default(),
+                ruler_cache: Cache::default(),
+                marker_cache: Cache::default(),
+                chain_cache: Cache::default(),
                 is_grid_enabled: true,
+                is_lane_info_enabled: false,
                 zoom: Self::INITIAL_ZOOM,
                 pan: Self::INITIAL_PAN,
-                bars: IntervalTree::new(),
-                started_bars: vec![],
-                channel_map: vec![],
+                scroll: 0.0,
+                lane_height: Self::INITIAL_LANE_HEIGHT,
+                modifiers: keyboard::Modifiers::default(),
+                model: TraceModel::new(),
+                paged_bars: vec![],
+                paged_range: 0..0,
+                styles: vec![],
+                color_overrides: HashMap::new(),
+                layout: Layout::default(),
+                priorities: HashMap::new(),
+                markers: vec![],
+                chain: vec![],
+                palette: Theme::default().palette(),
+                time_format: TimeFormat::default(),
                 status: String::new(),
-                min: 0,
-                max: 0,
                 width: 0,
+                height: 0.0,
             };
             s.set_zoom(1280.0 / 100.0);
             s.set_bars();
@@ -155,117 +705,416 @@ mod grid {
         }
 
         fn set_bars(&mut self) {
-            self.bars = IntervalTree::new();
-
-            // let mut rng = rand::thread_rng();
-            // for j in (0..1000).into_iter().step_by(100) {
-            //     for i in 0..10 {
-            //         let start = rng.gen_range(0..100) + j;
-            //         let end = rng.gen_range(100..200) + j;
-            //         self.bars.insert(
-            //             start as usize..end as usize,
-            //             Bar {
-            //                 start_ns: start * 1 as usize,
-            //                 end_ns: Some(end * 1 as usize),
-            //                 isr: format!("{}", i),
-            //                 channel: i,
-            //             },
-            //         );
-            //     }
-            // }
-        }
-
-        pub fn add_event(&mut self, timestamp: usize, event: EventType) {
-            let timestamp = timestamp - 0;
-            self.max = self.max.max(timestamp);
-            self.min = self.min.min(timestamp);
-            match event {
-                EventType::Overflow => (),
-                EventType::Task { name, action } => {
-                    match action {
-                        rtic_scope_api::TaskAction::Entered => {
-                            let channel = if let Some((index, _name)) =
-                                self.channel_map.iter().find_position(|c| *c == &name)
-                            {
-                                index
-                            } else {
-                                self.channel_map.push(name.clone());
-                                self.channel_map.len() - 1
-                            };
-                            self.started_bars.push(Bar {
-                                start_ns: timestamp,
-                                end_ns: None,
-                                isr: name,
-                                channel,
-                            });
-                        }
-                        rtic_scope_api::TaskAction::Exited => {
-                            let mut found = None;
-                            for (i, bar) in self.started_bars.iter().enumerate() {
-                                if bar.start_ns < timestamp && bar.isr == name {
-                                    found = Some(i);
-                                    break;
-                                }
-                            }
-                            if let Some(found) = found {
-                                let mut bar = self.started_bars.remove(found);
-                                bar.end_ns = Some(timestamp);
-                                self.bars.insert(bar.start_ns..timestamp, bar);
-                            }
-                        }
-                        rtic_scope_api::TaskAction::Returned => (),
-                    };
+            self.model.clear();
+            self.styles.clear();
+            self.chain.clear();
+            self.layout = Layout::new(&[], self.layout.order());
+            self.paged_bars.clear();
+            self.paged_range = 0..0;
+        }
 
-                    let screen_start = 0f32;
-                    let screen_end = self.width as f32;
-                    let start = self.min as f32;
-                    let end = self.max as f32;
+        pub fn ingest(&mut self, chunk: &EventChunk) {
+            self.model.ingest(chunk);
+            let lane_count = self.styles.len();
+            while self.styles.len() < self.model.lanes().len() {
+                let style = self.lane_style(&self.model.lanes()[self.styles.len()].name);
+                self.styles.push(style);
+            }
+            if self.styles.len() > lane_count {
+                self.apply_priorities(lane_count);
+                self.set_lane_order(self.layout.order());
+            }
+            if let Some(e) = self.model.take_spill_error() {
+                self.set_status(format!("Error spilling evicted bars {:?}", e));
+            }
+            self.zoom_to_fit();
+        }
 
-                    // start = screen_start / zoom - pan
-                    // end = screen_end / zoom - pan
-                    // start - end = screen_start / zoom - screen_end / zoom
+        /// Replaces the trace with the given one and fits it into the view.
+        pub(crate) fn load(&mut self, model: TraceModel) {
+            self.set_bars();
+            self.model = model;
+            self.styles = self
+                .model
+                .lanes()
+                .iter()
+                .map(|lane| self.lane_style(&lane.name))
+                .collect();
+            self.apply_priorities(0);
+            self.set_lane_order(self.layout.order());
+            self.zoom_to_fit();
+        }
+
+        fn zoom_to_fit(&mut self) {
+            let (start, end) = match self.model.range() {
+                Some((start, end)) if start < end => (start as f64, end as f64),
+                _ => return,
+            };
+            let screen_start = 0f64;
+            let screen_end = self.width as f64;
+
+            // start = screen_start / zoom - pan
+            // end = screen_end / zoom - pan
+            // start - end = screen_start / zoom - screen_end / zoom
+
+            let zoom = (screen_start - screen_end) / (start - end);
+            let pan = screen_start / zoom - start;
+
+            self.set_zoom(zoom);
+            self.set_pan(pan);
+            self.clear_caches();
+        }
+
+        pub(crate) fn set_retention(&mut self, retention: Retention) {
+            if let Err(e) = self.model.set_retention(retention) {
+                self.set_status(format!("Error creating spill file {:?}", e));
+            }
+            self.paged_range = 0..0;
+            self.clear_caches();
+        }
+
+        pub(crate) fn set_time_format(&mut self, time_format: TimeFormat) {
+            self.time_format = time_format;
+            self.clear_caches();
+        }
+
+        /// Reads the evicted bars in the visible range back from the spill file when zoomed in
+        /// far enough to draw individual bars.
+        fn page_in(&mut self) {
+            let (start, end) = self.visible_range();
+            let (start, end) = (start.max(0.0) as usize, end.max(0.0) as usize);
+            let is_zoomed_in = 1.0 / self.zoom < Summary::BASE_BUCKET_NS as f64;
+            if !is_zoomed_in || start >= self.model.evicted_before() {
+                self.paged_bars.clear();
+                self.paged_range = 0..0;
+                return;
+            }
+            if self.paged_range.start <= start && end <= self.paged_range.end {
+                return;
+            }
 
-                    let zoom = (screen_start - screen_end) / (start - end);
-                    let pan = screen_start / zoom - start;
+            // Read some margin around the visible range so panning does not hit the disk.
+            let margin = end - start;
+            let range = start.saturating_sub(margin)..end + margin;
+            match self.model.read_spilled(range.clone()) {
+                Ok(bars) => {
+                    self.paged_bars = bars;
+                    self.paged_range = range;
+                }
+                Err(e) => self.set_status(format!("Error reading spilled bars {:?}", e)),
+            }
+            self.bar_cache.clear();
+        }
+
+        fn lane_style(&self, name: &str) -> EventStyle {
+            EventStyle {
+                paint: Paint {
+                    color: self
+                        .color_overrides
+                        .get(name)
+                        .copied()
+                        .unwrap_or_else(|| task_color(name)),
+                },
+            }
+        }
 
-                    self.set_zoom(zoom);
-                    self.set_pan(pan);
-                    self.bar_cache.clear();
-                    self.grid_cache.clear();
+        /// Sets the priorities of the tasks from the given channel on which have one in the
+        /// application description.
+        fn apply_priorities(&mut self, from: usize) {
+            for channel in from..self.model.lanes().len() {
+                let name = &self.model.lanes()[channel].name;
+                if let Some(priority) = layout::priority(&self.priorities, name) {
+                    self.model.set_priority(channel, Some(priority));
                 }
-                EventType::Unknown(_) => (),
-                EventType::Unmappable(_, _) => (),
-                EventType::Invalid(_) => (),
             }
         }
 
+        /// Sets the priorities of tasks by name and rearranges the lanes, returning how many
+        /// tasks of the trace have one.
+        pub(crate) fn set_priorities(&mut self, priorities: HashMap<String, u8>) -> usize {
+            self.priorities = priorities;
+            self.apply_priorities(0);
+            self.set_lane_order(self.layout.order());
+            self.model
+                .lanes()
+                .iter()
+                .filter(|lane| layout::priority(&self.priorities, &lane.name).is_some())
+                .count()
+        }
+
+        pub(crate) fn priorities(&self) -> &HashMap<String, u8> {
+            &self.priorities
+        }
+
+        pub(crate) fn lane_order(&self) -> LaneOrder {
+            self.layout.order()
+        }
+
+        pub(crate) fn set_lane_order(&mut self, order: LaneOrder) {
+            self.layout = Layout::new(self.model.lanes(), order);
+            self.set_scroll(self.scroll);
+            self.clear_caches();
+        }
+
+        pub(crate) fn set_color_overrides(&mut self, overrides: HashMap<String, Color>) {
+            self.color_overrides = overrides;
+            self.styles = self
+                .model
+                .lanes()
+                .iter()
+                .map(|lane| self.lane_style(&lane.name))
+                .collect();
+            self.clear_caches();
+        }
+
+        pub(crate) fn color_overrides(&self) -> &HashMap<String, Color> {
+            &self.color_overrides
+        }
+
+        pub(crate) fn view_state(&self) -> View {
+            View {
+                zoom: self.zoom,
+                pan: self.pan,
+                scroll: self.scroll,
+                lane_height: self.lane_height,
+                is_grid_enabled: self.is_grid_enabled,
+                is_lane_info_enabled: self.is_lane_info_enabled,
+                lane_order: self.layout.order(),
+            }
+        }
+
+        pub(crate) fn markers(&self) -> &[Marker] {
+            &self.markers
+        }
+
+        /// Adds a bookmark if `start` and `end` are equal, otherwise an annotation over the range.
+        fn add_marker(&mut self, start: u64, end: u64) {
+            let marker = Marker {
+                start,
+                end,
+                text: String::new(),
+            };
+            let is_bookmark = marker.is_bookmark();
+            let count = self
+                .markers
+                .iter()
+                .filter(|marker| marker.is_bookmark() == is_bookmark)
+                .count();
+            let text = if is_bookmark {
+                format!("Bookmark {}", count + 1)
+            } else {
+                format!("Annotation {}", count + 1)
+            };
+
+            let index = self.markers.partition_point(|marker| marker.start <= start);
+            self.markers.insert(index, Marker { text, ..marker });
+            self.marker_cache.clear();
+        }
+
+        pub(crate) fn set_marker_text(&mut self, index: usize, text: String) {
+            self.markers[index].text = text;
+            self.marker_cache.clear();
+        }
+
+        pub(crate) fn remove_marker(&mut self, index: usize) {
+            self.markers.remove(index);
+            self.marker_cache.clear();
+        }
+
+        /// Centers the timeline on the marker, zooming out if an annotation does not fit.
+        pub(crate) fn go_to_marker(&mut self, index: usize) {
+            let Marker { start, end, .. } = self.markers[index];
+            let length = (end - start) as f64;
+            // Leave a margin around the annotation.
+            if length * self.zoom > self.width as f64 * 0.9 {
+                self.set_zoom(self.width as f64 * 0.9 / length);
+            }
+            self.center_on((start as f64 + end as f64) / 2.0);
+        }
+
+        /// Links the activations of the given instances of a chain by arrows.
+        pub(crate) fn set_chain(&mut self, instances: Vec<Instance>) {
+            self.chain = instances;
+            self.chain_cache.clear();
+        }
+
+        /// Replaces the trace and the view with the ones of the session. The color overrides
+        /// are set separately.
+        pub(crate) fn open_session(&mut self, session: &Session) {
+            self.set_bars();
+            session.restore(&mut self.model);
+            self.styles = self
+                .model
+                .lanes()
+                .iter()
+                .map(|lane| self.lane_style(&lane.name))
+                .collect();
+            self.apply_priorities(0);
+            self.set_lane_order(session.view.lane_order);
+            if let Some(e) = self.model.take_spill_error() {
+                self.set_status(format!("Error spilling evicted bars {:?}", e));
+            }
+
+            let view = &session.view;
+            self.set_zoom(view.zoom);
+            self.set_pan(view.pan);
+            self.lane_height = view
+                .lane_height
+                .max(Self::MIN_LANE_HEIGHT)
+                .min(Self::MAX_LANE_HEIGHT);
+            self.set_scroll(view.scroll);
+            self.is_grid_enabled = view.is_grid_enabled;
+            self.is_lane_info_enabled = view.is_lane_info_enabled;
+            self.markers = session.markers.clone();
+            self.markers.sort_by_key(|marker| marker.start);
+            self.page_in();
+            self.clear_caches();
+        }
+
+        /// Returns the range of the trace which is currently visible in ns.
+        pub(crate) fn visible_range(&self) -> (f64, f64) {
+            (self.x_to_ns(0.0), self.x_to_ns(self.width as f32))
+        }
+
+        /// Returns the range of the whole trace in ns.
+        pub(crate) fn trace_range(&self) -> (u64, u64) {
+            let (start, end) = self.model.range().unwrap_or((0, 0));
+            (start as u64, end as u64)
+        }
+
+        /// Returns the zoom in px per ns and the pan in ns, which map timestamps to the x axis.
+        pub(crate) fn transform(&self) -> (f64, f64) {
+            (self.zoom, self.pan)
+        }
+
+        /// Sets the zoom and pan of another grid. Unlike panning, the left edge may be before
+        /// the start of the trace, so a shifted trace can be lined up with another one.
+        pub(crate) fn set_transform(&mut self, zoom: f64, pan: f64) {
+            self.zoom = zoom.max(Self::MIN_ZOOM);
+            self.pan = pan;
+            self.page_in();
+            self.clear_caches();
+        }
+
+        /// Pans the timeline such that the given timestamp is in the center.
+        pub(crate) fn center_on(&mut self, ns: f64) {
+            self.set_pan(self.width as f64 / (2.0 * self.zoom) - ns);
+            self.page_in();
+            self.clear_caches();
+        }
+
+        /// Returns the fraction of each of the given number of bins of the trace which is
+        /// covered by bars, per lane.
+        pub(crate) fn density(&self, bins: usize) -> Vec<Vec<f32>> {
+            self.model.density(bins)
+        }
+
+        pub(crate) fn lane_colors(&self) -> Vec<Color> {
+            self.styles.iter().map(|style| style.paint.color).collect()
+        }
+
+        pub(crate) fn set_theme(&mut self, theme: Theme) {
+            self.palette = theme.palette();
+            self.clear_caches();
+        }
+
         fn update_zoom(&mut self, delta: f32) {
-            self.zoom *= 1.0 + (delta / 1e2);
-            self.zoom = self.zoom.max(1e-8);
+            self.zoom *= 1.0 + (delta as f64 / 1e2);
+            self.zoom = self.zoom.max(Self::MIN_ZOOM);
         }
 
-        fn set_zoom(&mut self, zoom: f32) {
+        fn set_zoom(&mut self, zoom: f64) {
             // px / ns
             self.zoom = zoom;
-            self.zoom = self.zoom.max(1e-8);
+            self.zoom = self.zoom.max(Self::MIN_ZOOM);
         }
 
         fn update_pan(&mut self, delta: f32) {
-            self.pan += delta / self.zoom; // px / (px / ns) = ns
+            self.pan += delta as f64 / self.zoom; // px / (px / ns) = ns
             self.pan = self.pan.min(0.5);
         }
 
-        fn set_pan(&mut self, pan: f32) {
+        fn set_pan(&mut self, pan: f64) {
             self.pan = pan;
             self.pan = self.pan.min(0.5);
         }
 
+        /// Returns the x coordinate of the timestamp relative to the left edge of the timeline.
+        ///
+        /// The timestamp is made relative to the view origin before it is scaled, and only the
+        /// result, which is small for anything on screen, is narrowed to f32. This keeps ns
+        /// detail exact hours into a trace.
+        fn ns_to_x(&self, ns: u64) -> f32 {
+            ((ns as f64 + self.pan) * self.zoom) as f32 // (ns + ns) * px / ns = px
+        }
+
+        /// Returns the timestamp at the given x coordinate relative to the left edge of the
+        /// timeline.
+        fn x_to_ns(&self, x: f32) -> f64 {
+            x as f64 / self.zoom - self.pan // px / (px / ns) - ns = ns
+        }
+
+        fn update_scroll(&mut self, delta: f32) {
+            self.set_scroll(self.scroll - delta);
+        }
+
+        fn set_scroll(&mut self, scroll: f32) {
+            // Allow scrolling until the last lane is at the bottom of the visible area.
+            let content_height = self.layout.rows().len() as f32
+                * (self.lane_height + Self::LANE_PADDING)
+                + Self::OFFSET_TOP;
+            let max_scroll = (content_height - self.height).max(0.0);
+            self.scroll = scroll.max(0.0).min(max_scroll);
+        }
+
+        fn update_lane_height(&mut self, delta: f32) {
+            self.lane_height = (self.lane_height + delta)
+                .max(Self::MIN_LANE_HEIGHT)
+                .min(Self::MAX_LANE_HEIGHT);
+            self.set_scroll(self.scroll);
+        }
+
+        /// Returns the top of the lane of the given channel in px, relative to the canvas.
+        fn lane_y(&self, channel: usize) -> f32 {
+            self.row_y(self.layout.row(channel))
+        }
+
+        /// Returns the top of the row of the layout in px, relative to the canvas.
+        fn row_y(&self, row: usize) -> f32 {
+            row as f32 * (self.lane_height + Self::LANE_PADDING) + Self::OFFSET_TOP - self.scroll
+        }
+
+        /// Returns the name and the info of the row, which are shown in the gutter.
+        fn row_label(&self, row: &Row) -> (String, String) {
+            match row.channels[..] {
+                [channel] => {
+                    let lane = &self.model.lanes()[channel];
+                    (lane.name.clone(), lane_info(lane))
+                }
+                _ => (
+                    format!("Priority {}", row.priority.unwrap_or_default()),
+                    format!("{} tasks", row.channels.len()),
+                ),
+            }
+        }
+
+        fn clear_caches(&mut self) {
+            self.bar_cache.clear();
+            self.grid_cache.clear();
+            self.gutter_cache.clear();
+            self.ruler_cache.clear();
+            self.marker_cache.clear();
+            self.chain_cache.clear();
+        }
+
         pub(crate) fn reset_state(&mut self) {
             self.set_bars();
+            self.markers.clear();
             self.zoom = Self::INITIAL_ZOOM;
             self.pan = Self::INITIAL_PAN;
-            self.grid_cache.clear();
-            self.bar_cache.clear();
+            self.scroll = 0.0;
+            self.lane_height = Self::INITIAL_LANE_HEIGHT;
+            self.clear_caches();
         }
 
         pub(crate) fn toggle_grid(&mut self, enabled: bool) {
@@ -276,6 +1125,23 @@ mod grid {
             self.is_grid_enabled
         }
 
+        pub(crate) fn toggle_lane_info(&mut self, enabled: bool) {
+            self.is_lane_info_enabled = enabled;
+            self.gutter_cache.clear();
+        }
+
+        pub(crate) fn is_lane_info_visible(&self) -> bool {
+            self.is_lane_info_enabled
+        }
+
+        pub(crate) fn model(&self) -> &TraceModel {
+            &self.model
+        }
+
+        pub(crate) fn model_mut(&mut self) -> &mut TraceModel {
+            &mut self.model
+        }
+
         pub(crate) fn set_status(&mut self, status: impl AsRef<str>) {
             self.status = status.as_ref().to_owned();
         }
@@ -285,6 +1151,301 @@ mod grid {
         }
     }
 
+    impl Grid {
+        /// Draws a single bar with its label in the bar layer.
+        fn draw_bar(
+            &self,
+            frame: &mut Frame,
+            interval: Range<usize>,
+            channel: usize,
+            width: f32,
+            height: f32,
+        ) {
+            let bar_height = self.lane_height;
+            let y = self.lane_y(channel); // 1 * px + px
+            if y + bar_height < 0.0 || y > height {
+                return;
+            }
+
+            let lane = &self.model.lanes()[channel];
+            let style = self.styles[channel];
+            // Clip the bar to the visible area, as it may be far wider than the screen.
+            let start = self.ns_to_x(interval.start as u64).max(-1.0).min(width);
+            let length = self.ns_to_x(interval.end as u64).min(width + 1.0) - start;
+            frame.fill_rectangle(
+                Point::new(start, y),
+                Size::new(length, bar_height),
+                style.paint.color,
+            );
+
+            // Center the label in the visible part of the bar and skip it entirely if
+            // not even a single character fits.
+            let visible_start = start.max(0.0);
+            let visible_end = (start + length).min(width);
+            let text_size = 15f32.min(bar_height);
+            if let Some(label) = elide(&lane.name, visible_end - visible_start - 4.0, text_size) {
+                frame.fill_text(Text {
+                    content: label,
+                    position: Point::new((visible_start + visible_end) / 2.0, y + bar_height / 2.0),
+                    color: self.palette.bar_text,
+                    size: text_size,
+                    font: Font::Default,
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                });
+            }
+        }
+    }
+
+    impl Grid {
+        /// Draws the lanes, bars, labels and axis of the visible part of the trace, or of the
+        /// whole trace, into a scene whose timeline is `width` px wide.
+        ///
+        /// Unlike the canvas, the scene is not scrolled, so it contains all lanes.
+        pub(crate) fn export_scene(&self, is_full_trace: bool, width: f32) -> Scene {
+            let (start, end) = if is_full_trace {
+                let (start, end) = self.model.range().unwrap_or((0, 0));
+                (start as f64, end as f64)
+            } else {
+                self.visible_range()
+            };
+            let start = start.max(0.0);
+            let end = end.max(start + 1.0);
+            let zoom = width as f64 / (end - start); // px / ns
+
+            let total_width = Self::GUTTER_WIDTH + width;
+            let bar_height = self.lane_height;
+            let row_y = |row: usize| {
+                row as f32 * (self.lane_height + Self::LANE_PADDING) + Self::OFFSET_TOP
+            };
+            let lane_y = |channel: usize| row_y(self.layout.row(channel));
+            // Like `ns_to_x`, relative to the start of the exported range.
+            let ns_to_x = |ns: u64| Self::GUTTER_WIDTH + ((ns as f64 - start) * zoom) as f32;
+            let clip = |from: u64, to: u64| {
+                let from = ns_to_x(from).max(Self::GUTTER_WIDTH);
+                (from, ns_to_x(to).min(total_width) - from)
+            };
+
+            let lanes = self.model.lanes();
+            let rows = self.layout.rows();
+            let mut scene = Scene::new(total_width, row_y(rows.len()), self.palette.background);
+            let axis = Axis::new(zoom, Self::TICK_SPACING);
+
+            if self.is_grid_enabled {
+                for tick in axis.ticks(start..end) {
+                    let x = ns_to_x(tick.ns);
+                    let color = if tick.major {
+                        self.palette.grid
+                    } else {
+                        Color {
+                            a: 0.25,
+                            ..self.palette.grid
+                        }
+                    };
+                    scene.line((x, Self::RULER_HEIGHT), (x, scene.height), color);
+                }
+            }
+
+            let ns_per_px = 1.0 / zoom;
+            if ns_per_px >= Summary::BASE_BUCKET_NS as f64 {
+                let mut level = Summary::level_for(ns_per_px as u64);
+                let range = start as u64..end as u64;
+                if range.start < self.model.evicted_before() as u64 {
+                    level = level.max(Summary::RETAINED_LEVEL);
+                }
+                for (channel, lane) in lanes.iter().enumerate() {
+                    let color = self.styles[channel].paint.color;
+                    for block in lane.summary.blocks(level, range.clone()) {
+                        let (x, length) = clip(block.start, block.end);
+                        scene.rectangle(
+                            x,
+                            lane_y(channel),
+                            length.max(1.0),
+                            bar_height,
+                            Color {
+                                a: 0.4 + 0.6 * block.density(),
+                                ..color
+                            },
+                        );
+                    }
+                }
+            } else {
+                let range = start as usize..end as usize;
+                let paged_bars = self
+                    .paged_bars
+                    .iter()
+                    .filter(|bar| bar.start < range.end && range.start <= bar.end);
+                for bar in self.model.bars(range.clone()).chain(paged_bars) {
+                    let (x, length) = clip(bar.start as u64, bar.end as u64);
+                    let y = lane_y(bar.channel);
+                    scene.rectangle(
+                        x,
+                        y,
+                        length,
+                        bar_height,
+                        self.styles[bar.channel].paint.color,
+                    );
+
+                    let text_size = 15f32.min(bar_height);
+                    let name = &lanes[bar.channel].name;
+                    if let Some(label) = elide(name, length - 4.0, text_size) {
+                        scene.text(
+                            x + length / 2.0,
+                            y + bar_height / 2.0,
+                            label,
+                            text_size,
+                            self.palette.bar_text,
+                            Anchor::Middle,
+                        );
+                    }
+                }
+            }
+
+            scene.rectangle(
+                0.0,
+                0.0,
+                Self::GUTTER_WIDTH,
+                scene.height,
+                self.palette.gutter,
+            );
+            scene.line(
+                (Self::GUTTER_WIDTH, 0.0),
+                (Self::GUTTER_WIDTH, scene.height),
+                self.palette.grid,
+            );
+            for (index, row) in rows.iter().enumerate() {
+                let y = row_y(index);
+                if self.layout.starts_group(index) {
+                    let y = y - Self::LANE_PADDING / 2.0;
+                    scene.line((0.0, y), (total_width, y), self.palette.grid);
+                }
+                let swatch_height = bar_height / row.channels.len() as f32;
+                for (i, &channel) in row.channels.iter().enumerate() {
+                    scene.rectangle(
+                        4.0,
+                        y + i as f32 * swatch_height,
+                        Self::SWATCH_WIDTH,
+                        swatch_height,
+                        self.styles[channel].paint.color,
+                    );
+                }
+
+                let (name, info) = self.row_label(row);
+                let info_size = 12f32.min(bar_height);
+                let info_width = if self.is_lane_info_enabled {
+                    text_width(&info, info_size) + 4.0
+                } else {
+                    0.0
+                };
+                let name_start = 8.0 + Self::SWATCH_WIDTH;
+                let name_size = 15f32.min(bar_height);
+                if let Some(name) = elide(
+                    &name,
+                    Self::GUTTER_WIDTH - 4.0 - info_width - name_start,
+                    name_size,
+                ) {
+                    scene.text(
+                        name_start,
+                        y + bar_height / 2.0,
+                        name,
+                        name_size,
+                        self.palette.text,
+                        Anchor::Start,
+                    );
+                }
+                if self.is_lane_info_enabled {
+                    scene.text(
+                        Self::GUTTER_WIDTH - 4.0,
+                        y + bar_height / 2.0,
+                        info,
+                        info_size,
+                        self.palette.dimmed_text,
+                        Anchor::End,
+                    );
+                }
+            }
+
+            scene.rectangle(
+                0.0,
+                0.0,
+                total_width,
+                Self::RULER_HEIGHT,
+                self.palette.gutter,
+            );
+            scene.line(
+                (0.0, Self::RULER_HEIGHT),
+                (total_width, Self::RULER_HEIGHT),
+                self.palette.grid,
+            );
+            for tick in axis.ticks(start..end) {
+                let x = ns_to_x(tick.ns);
+                let length = if tick.major { 8.0 } else { 4.0 };
+                scene.line(
+                    (x, Self::RULER_HEIGHT - length),
+                    (x, Self::RULER_HEIGHT),
+                    self.palette.grid,
+                );
+                if tick.major {
+                    scene.text(
+                        x,
+                        Self::RULER_HEIGHT / 2.0 - 2.0,
+                        self.time_format
+                            .format_with_resolution(tick.ns, axis.major_ns),
+                        14.0,
+                        self.palette.text,
+                        Anchor::Middle,
+                    );
+                }
+            }
+
+            for marker in &self.markers {
+                if marker.end < start as u64 || marker.start > end as u64 {
+                    continue;
+                }
+                let (x, length) = clip(marker.start, marker.end);
+                if marker.is_bookmark() {
+                    scene.line((x, 0.0), (x, scene.height), self.palette.marker);
+                } else {
+                    scene.rectangle(
+                        x,
+                        Self::RULER_HEIGHT,
+                        length,
+                        scene.height - Self::RULER_HEIGHT,
+                        Color {
+                            a: 0.15,
+                            ..self.palette.marker
+                        },
+                    );
+                }
+                let max_width = if marker.is_bookmark() {
+                    Self::MARKER_LABEL_WIDTH
+                } else {
+                    length - 6.0
+                };
+                if let Some(label) = elide(&marker.text, max_width, 14.0) {
+                    scene.text(
+                        x + 3.0,
+                        Self::RULER_HEIGHT + 10.0,
+                        label,
+                        14.0,
+                        self.palette.marker,
+                        Anchor::Start,
+                    );
+                }
+            }
+
+            scene
+        }
+    }
+
+    /// Returns the priority and activation count of the lane.
+    fn lane_info(lane: &Lane) -> String {
+        match lane.priority {
+            Some(priority) => format!("P{} {}x", priority, lane.activations),
+            None => format!("{}x", lane.activations),
+        }
+    }
+
     impl<'a> canvas::Program<Message> for Grid {
         fn update(
             &mut self,
@@ -292,10 +1453,30 @@ mod grid {
             bounds: Rectangle,
             cursor: Cursor,
         ) -> (event::Status, Option<Message>) {
-            self.width = bounds.size().width as usize;
+            self.width = (bounds.size().width - Self::GUTTER_WIDTH).max(0.0) as usize;
+            self.height = bounds.size().height;
 
             if let Event::Mouse(mouse::Event::ButtonReleased(_)) = event {
-                self.interaction = Interaction::None;
+                let interaction = std::mem::replace(&mut self.interaction, Interaction::None);
+                if let (Interaction::Selecting { origin }, Some(position)) =
+                    (interaction, cursor.position())
+                {
+                    let x_to_ns =
+                        |x: f32| self.x_to_ns(x - bounds.x - Self::GUTTER_WIDTH).max(0.0) as u64;
+                    let (start, end) = if (position.x - origin.x).abs() < Self::MIN_DRAG {
+                        (x_to_ns(origin.x), x_to_ns(origin.x))
+                    } else {
+                        let (start, end) = (x_to_ns(origin.x), x_to_ns(position.x));
+                        (start.min(end), start.max(end))
+                    };
+                    self.add_marker(start, end);
+                    return (event::Status::Captured, Some(Message::MarkersChanged));
+                }
+            }
+
+            if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+                self.modifiers = modifiers;
+                return (event::Status::Ignored, None);
             }
 
             let cursor_position = if let Some(position) = cursor.position() {
@@ -304,11 +1485,24 @@ mod grid {
                 return (event::Status::Ignored, None);
             };
 
+            // Only an ongoing interaction may continue outside of the canvas.
+            if !cursor.is_over(&bounds) && matches!(self.interaction, Interaction::None) {
+                return (event::Status::Ignored, None);
+            }
+
             match event {
                 Event::Mouse(mouse_event) => match mouse_event {
                     mouse::Event::ButtonPressed(button) => {
                         let message = match button {
-                            mouse::Button::Left => None,
+                            mouse::Button::Left
+                                if cursor_position.x - bounds.x >= Self::GUTTER_WIDTH =>
+                            {
+                                self.interaction = Interaction::Selecting {
+                                    origin: cursor_position,
+                                };
+
+                                None
+                            }
                             mouse::Button::Right => {
                                 self.interaction = Interaction::Panning {
                                     start: cursor_position,
@@ -324,16 +1518,18 @@ mod grid {
                     mouse::Event::CursorMoved { .. } => {
                         let message = match self.interaction {
                             Interaction::Panning { start } => {
-                                self.update_pan((cursor_position - start).x);
+                                let delta = cursor_position - start;
+                                self.update_pan(delta.x);
+                                self.update_scroll(delta.y);
+                                self.page_in();
 
-                                self.bar_cache.clear();
-                                self.grid_cache.clear();
+                                self.clear_caches();
 
                                 self.interaction = Interaction::Panning {
                                     start: cursor_position,
                                 };
 
-                                None
+                                Some(Message::ViewChanged)
                             }
                             _ => None,
                         };
@@ -345,15 +1541,39 @@ mod grid {
 
                         (event_status, message)
                     }
-                    mouse::Event::WheelScrolled { delta } => match delta {
-                        mouse::ScrollDelta::Lines { y, .. }
-                        | mouse::ScrollDelta::Pixels { y, .. } => {
-                            self.update_zoom(y);
-                            self.bar_cache.clear();
-                            self.grid_cache.clear();
-                            (event::Status::Captured, None)
+                    mouse::Event::WheelScrolled { delta } => {
+                        if self.modifiers.shift() {
+                            // Some platforms turn a shifted vertical scroll into a horizontal one.
+                            let delta = match delta {
+                                mouse::ScrollDelta::Lines { x, y } => {
+                                    (if y != 0.0 { y } else { x })
+                                        * (self.lane_height + Self::LANE_PADDING)
+                                }
+                                mouse::ScrollDelta::Pixels { x, y } => {
+                                    if y != 0.0 {
+                                        y
+                                    } else {
+                                        x
+                                    }
+                                }
+                            };
+                            self.update_scroll(delta);
+                        } else {
+                            match delta {
+                                mouse::ScrollDelta::Lines { y, .. }
+                                | mouse::ScrollDelta::Pixels { y, .. } => {
+                                    if self.modifiers.control() {
+                                        self.update_lane_height(y);
+                                    } else {
+                                        self.update_zoom(y);
+                                        self.page_in();
+                                    }
+                                }
+                            }
                         }
-                    },
+                        self.clear_caches();
+                        (event::Status::Captured, Some(Message::ViewChanged))
+                    }
                     _ => (event::Status::Ignored, None),
                 },
                 _ => (event::Status::Ignored, None),
@@ -362,49 +1582,60 @@ mod grid {
 
         fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
             let size = bounds.size();
-            let cursor_x = cursor.position().map(|c| c.x).unwrap_or(0.0);
-            let cursor_y = cursor.position().map(|c| c.y).unwrap_or(0.0);
-            let logical_start = (0.0 - self.pan * self.zoom) / self.zoom;
-            let logical_end = (bounds.size().width - self.pan * self.zoom) / self.zoom;
-            let logical_cursor_x = ((cursor_x - self.pan * self.zoom) / self.zoom) as usize;
-
-            let bar_height = 20.0;
-            let bar_padding = 8.0;
-            let offset_top = 20.0;
+            let width = (size.width - Self::GUTTER_WIDTH).max(0.0);
+            let cursor_position = cursor.position_in(&bounds);
+            let cursor_x = cursor_position
+                .map(|c| c.x - Self::GUTTER_WIDTH)
+                .unwrap_or(-1.0);
+            let cursor_y = cursor_position.map(|c| c.y).unwrap_or(0.0);
+            let logical_start = self.x_to_ns(0.0);
+            let logical_end = self.x_to_ns(width);
+            let logical_cursor_x = self.x_to_ns(cursor_x) as usize;
+
+            let bar_height = self.lane_height;
+            // The times of the tooltip are shown as precise as the cursor can point.
+            let ns_per_px = (1.0 / self.zoom) as u64;
 
             let overlay = {
                 let mut frame = Frame::new(size);
+                frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
 
-                for bar in self.bars.find(logical_cursor_x..logical_cursor_x + 1) {
-                    let y = bar.data().channel as f32 * (bar_height + bar_padding) + offset_top; // 1 * px + px
+                for bar in self
+                    .model
+                    .bars(logical_cursor_x..logical_cursor_x + 1)
+                    .filter(|_| cursor_x >= 0.0)
+                {
+                    let y = self.lane_y(bar.channel); // 1 * px + px
 
                     if y < cursor_y && cursor_y <= y + bar_height {
-                        let start = (bar.interval().start as f32 * self.zoom
-                            + self.pan * self.zoom)
-                            .min(size.width); // ns * px / ns + ns = px
-                        let length = (bar.interval().end - bar.interval().start) as f32 * self.zoom; // ns * px / ns = px
-                        let y = bar.data().channel as f32 * (bar_height + bar_padding) + offset_top; // 1 * px + px
+                        let start = self.ns_to_x(bar.start as u64).max(-1.0).min(width);
+                        let length = self.ns_to_x(bar.end as u64).min(width + 1.0) - start;
                         frame.fill_rectangle(
                             Point::new(start, y),
                             Size::new(length, bar_height + bar_height),
-                            Color::WHITE,
+                            self.palette.tooltip_background,
                         );
                         frame.stroke(
                             &Path::rectangle(
                                 Point::new(start, y),
                                 Size::new(length, bar_height * 2.0),
                             ),
-                            Stroke::default().with_color(Color::BLACK).with_width(1.5),
+                            Stroke::default()
+                                .with_color(self.palette.tooltip_border)
+                                .with_width(1.5),
                         );
                         frame.fill_text(Text {
                             content: format!(
-                                "{} - {} : {}",
-                                to_si_time(bar.interval().start),
-                                to_si_time(bar.interval().end),
-                                bar.data().isr
+                                "{} - {} ({}) : {}",
+                                self.time_format
+                                    .format_with_resolution(bar.start as u64, ns_per_px),
+                                self.time_format
+                                    .format_with_resolution(bar.end as u64, ns_per_px),
+                                self.time_format.format(bar.duration() as u64),
+                                self.model.lanes()[bar.channel].name
                             ),
                             position: Point::new(start + 2.0, y + bar_height + bar_height / 2.0),
-                            color: Color::BLACK,
+                            color: self.palette.tooltip_text,
                             size: 15.0,
                             font: Font::Default,
                             horizontal_alignment: alignment::Horizontal::Left,
@@ -414,110 +1645,323 @@ mod grid {
                     }
                 }
 
+                if let (Interaction::Selecting { origin }, Some(_)) =
+                    (&self.interaction, cursor_position)
+                {
+                    let origin_x = origin.x - bounds.x - Self::GUTTER_WIDTH;
+                    let start = origin_x.min(cursor_x).max(0.0);
+                    let end = origin_x.max(cursor_x).min(width);
+                    frame.fill_rectangle(
+                        Point::new(start, Self::RULER_HEIGHT),
+                        Size::new(end - start, size.height - Self::RULER_HEIGHT),
+                        Color {
+                            a: 0.15,
+                            ..self.palette.marker
+                        },
+                    );
+                }
+
                 frame.into_geometry()
             };
 
             let bar = self.bar_cache.draw(size, |frame| {
-                let mut isrs = HashMap::<usize, EventStyle>::new();
-                let palette: &[Color] = &[
-                    Color::from_rgb8(0, 18, 25),
-                    Color::from_rgb8(0, 95, 115),
-                    Color::from_rgb8(10, 147, 150),
-                    Color::from_rgb8(148, 210, 189),
-                    Color::from_rgb8(233, 216, 166),
-                    Color::from_rgb8(238, 155, 0),
-                    Color::from_rgb8(202, 103, 2),
-                    Color::from_rgb8(187, 62, 3),
-                    Color::from_rgb8(174, 32, 18),
-                    Color::from_rgb8(155, 34, 38),
-                ];
-
-                // let t = std::time::Instant::now();
-                for bar in self
-                    .bars
-                    .find(logical_start.max(0.0) as usize..logical_end.min(f32::MAX) as usize)
-                {
-                    let pot_isr = isrs.get(&bar.data().channel).cloned();
-                    let (channel, isr) = if let Some(isr) = pot_isr {
-                        (bar.data().channel, isr)
-                    } else {
-                        let isr = EventStyle {
-                            paint: Paint {
-                                color: palette[isrs.len()],
-                            },
-                        };
-                        isrs.insert(bar.data().channel, isr);
-                        (bar.data().channel, isrs[&bar.data().channel])
-                    };
-                    let start = (bar.interval().start as f32 * self.zoom + self.pan * self.zoom)
-                        .min(size.width); // ns * px / ns + ns = px
-                    let length = (bar.interval().end - bar.interval().start) as f32 * self.zoom; // ns * px / ns = px
-                    let y = channel as f32 * (bar_height + bar_padding) + offset_top; // 1 * px + px
-                    frame.fill_rectangle(
-                        Point::new(start, y),
-                        Size::new(length, bar_height),
-                        isr.paint.color,
-                    );
-                    frame.fill_text(Text {
-                        content: format!("{}", bar.data().isr),
-                        position: Point::new(start + 2.0, y + bar_height / 2.0),
-                        color: Color::BLACK,
-                        size: 15.0,
-                        font: Font::Default,
-                        horizontal_alignment: alignment::Horizontal::Left,
-                        vertical_alignment: alignment::Vertical::Center,
-                    });
-                }
-                // println!("{:?}", t.elapsed());
+                frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
+
+                let ns_per_px = 1.0 / self.zoom;
+                if ns_per_px >= Summary::BASE_BUCKET_NS as f64 {
+                    // Individual bars are at most a few pixels wide, so draw the coverage of each
+                    // lane instead, which takes time proportional to the width of the screen.
+                    let mut level = Summary::level_for(ns_per_px as u64);
+                    let range = logical_start.max(0.0) as u64..logical_end.max(0.0) as u64;
+                    if range.start < self.model.evicted_before() as u64 {
+                        level = level.max(Summary::RETAINED_LEVEL);
+                    }
+                    for (channel, lane) in self.model.lanes().iter().enumerate() {
+                        let y = self.lane_y(channel);
+                        if y + bar_height < 0.0 || y > size.height {
+                            continue;
+                        }
+
+                        let color = self.styles[channel].paint.color;
+                        for block in lane.summary.blocks(level, range.clone()) {
+                            let start = self.ns_to_x(block.start).max(-1.0);
+                            let length = self.ns_to_x(block.end).min(width + 1.0) - start;
+                            frame.fill_rectangle(
+                                Point::new(start, y),
+                                Size::new(length.max(1.0), bar_height),
+                                Color {
+                                    a: 0.4 + 0.6 * block.density(),
+                                    ..color
+                                },
+                            );
+                        }
+                    }
+                    return;
+                }
+
+                let range = logical_start.max(0.0) as usize..logical_end.max(0.0) as usize;
+                for bar in self.model.bars(range.clone()) {
+                    self.draw_bar(frame, bar.start..bar.end, bar.channel, width, size.height);
+                }
+                for bar in &self.paged_bars {
+                    if bar.start < range.end && range.start <= bar.end {
+                        self.draw_bar(frame, bar.start..bar.end, bar.channel, width, size.height);
+                    }
+                }
             });
 
-            if self.is_grid_enabled {
-                let grid = self.grid_cache.draw(bounds.size(), |frame| {
-                    let size = bounds.size();
-                    // Find the correct spacing of all the bars.
-                    let mut spacing = self.zoom * 1.0; // px / ns * ns = px
-                    while size.width as f32 / spacing > 10.0 {
-                        // px / px = 1
-                        spacing *= 10.0; // px
+            // An arrow leads from each activation of a chain to the one it caused. It starts at
+            // the end of the cause, or where the next activation preempts it.
+            let chain = self.chain_cache.draw(size, |frame| {
+                frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
+                let stroke = || {
+                    Stroke::default()
+                        .with_color(self.palette.chain)
+                        .with_width(1.5)
+                };
+
+                let end = self.chain.partition_point(|instance| {
+                    instance.bars.first().map_or(0, |bar| bar.start) as f64 <= logical_end
+                });
+                let visible = self.chain[..end].iter().filter(|instance| {
+                    instance.bars.last().map_or(0, |bar| bar.end) as f64 >= logical_start
+                });
+                for instance in visible {
+                    for pair in instance.bars.windows(2) {
+                        let (cause, effect) = (pair[0], pair[1]);
+                        let from = Point::new(
+                            self.ns_to_x(cause.end.min(effect.start) as u64),
+                            self.lane_y(cause.channel) + bar_height / 2.0,
+                        );
+                        let to = Point::new(
+                            self.ns_to_x(effect.start as u64),
+                            self.lane_y(effect.channel) + bar_height / 2.0,
+                        );
+                        frame.stroke(&Path::line(from, to), stroke());
+
+                        let length = from.distance(to);
+                        if length < 1.0 {
+                            continue;
+                        }
+                        let (dx, dy) = ((to.x - from.x) / length, (to.y - from.y) / length);
+                        for side in [-1.0, 1.0] {
+                            let (x, y) =
+                                (-dx * 0.87 - side * dy * 0.5, -dy * 0.87 + side * dx * 0.5);
+                            frame.stroke(
+                                &Path::line(to, Point::new(to.x + x * 6.0, to.y + y * 6.0)),
+                                stroke(),
+                            );
+                        }
                     }
+                }
+            });
 
-                    let y = size.height as f32 - 30.0;
+            // The lane names stay in place while the timeline is panned, so they are drawn on
+            // top of the bars which are scrolled underneath them.
+            let gutter = self.gutter_cache.draw(size, |frame| {
+                frame.fill_rectangle(
+                    Point::ORIGIN,
+                    Size::new(Self::GUTTER_WIDTH, size.height),
+                    self.palette.gutter,
+                );
+                frame.stroke(
+                    &Path::line(
+                        Point::new(Self::GUTTER_WIDTH, 0.0),
+                        Point::new(Self::GUTTER_WIDTH, size.height),
+                    ),
+                    Stroke::default().with_color(self.palette.grid),
+                );
+
+                for (index, row) in self.layout.rows().iter().enumerate() {
+                    let y = self.row_y(index);
+                    if y + bar_height < 0.0 || y > size.height {
+                        continue;
+                    }
 
-                    let mut x = self.pan * self.zoom;
-                    while x < size.width {
-                        // Draw the grid.
+                    // Groups of priorities are separated by a line across the whole timeline,
+                    // which runs through the padding between the lanes.
+                    if self.layout.starts_group(index) {
+                        let y = y - Self::LANE_PADDING / 2.0;
                         frame.stroke(
-                            &Path::line(Point::new(x, 0.0), Point::new(x, y as f32)),
-                            Stroke::default().with_color(Color::BLACK),
+                            &Path::line(Point::new(0.0, y), Point::new(size.width, y)),
+                            Stroke::default().with_color(self.palette.grid),
                         );
+                    }
+
+                    // The tasks which share a lane share its swatch.
+                    let swatch_height = bar_height / row.channels.len() as f32;
+                    for (i, &channel) in row.channels.iter().enumerate() {
+                        frame.fill_rectangle(
+                            Point::new(4.0, y + i as f32 * swatch_height),
+                            Size::new(Self::SWATCH_WIDTH, swatch_height),
+                            self.styles[channel].paint.color,
+                        );
+                    }
+
+                    let (name, info) = self.row_label(row);
+                    let info_size = 12f32.min(bar_height);
+                    let info_width = if self.is_lane_info_enabled {
+                        text_width(&info, info_size) + 4.0
+                    } else {
+                        0.0
+                    };
 
-                        // Draw all the grid timescale annotations.
+                    let name_start = 8.0 + Self::SWATCH_WIDTH;
+                    let name_size = 15f32.min(bar_height);
+                    if let Some(name) = elide(
+                        &name,
+                        Self::GUTTER_WIDTH - 4.0 - info_width - name_start,
+                        name_size,
+                    ) {
+                        frame.fill_text(Text {
+                            content: name,
+                            position: Point::new(name_start, y + bar_height / 2.0),
+                            color: self.palette.text,
+                            size: name_size,
+                            font: Font::Default,
+                            horizontal_alignment: alignment::Horizontal::Left,
+                            vertical_alignment: alignment::Vertical::Center,
+                        });
+                    }
 
-                        // Find the number to display.
-                        let ns = (-self.pan + x / self.zoom).round() as usize; // --ns + px / (px / ns) = ns
+                    if self.is_lane_info_enabled {
+                        frame.fill_text(Text {
+                            content: info,
+                            position: Point::new(Self::GUTTER_WIDTH - 4.0, y + bar_height / 2.0),
+                            color: self.palette.dimmed_text,
+                            size: info_size,
+                            font: Font::Default,
+                            horizontal_alignment: alignment::Horizontal::Right,
+                            vertical_alignment: alignment::Vertical::Center,
+                        });
+                    }
+                }
+            });
+
+            let axis = Axis::new(self.zoom, Self::TICK_SPACING);
+
+            // The ruler stays at the top while the lanes are scrolled underneath it. It also
+            // covers the top of the gutter, so lane names do not scroll into its corner.
+            let ruler = self.ruler_cache.draw(size, |frame| {
+                frame.fill_rectangle(
+                    Point::ORIGIN,
+                    Size::new(size.width, Self::RULER_HEIGHT),
+                    self.palette.gutter,
+                );
+                frame.stroke(
+                    &Path::line(
+                        Point::new(0.0, Self::RULER_HEIGHT),
+                        Point::new(size.width, Self::RULER_HEIGHT),
+                    ),
+                    Stroke::default().with_color(self.palette.grid),
+                );
+                frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
+
+                for tick in axis.ticks(logical_start..logical_end) {
+                    let x = self.ns_to_x(tick.ns);
+                    let length = if tick.major { 8.0 } else { 4.0 };
+                    frame.stroke(
+                        &Path::line(
+                            Point::new(x, Self::RULER_HEIGHT - length),
+                            Point::new(x, Self::RULER_HEIGHT),
+                        ),
+                        Stroke::default().with_color(self.palette.grid),
+                    );
 
+                    if tick.major {
                         frame.fill_text(Text {
-                            content: to_si_time(ns),
-                            position: Point::new(x, y),
-                            color: Color::BLACK,
-                            size: 18.0,
+                            content: self
+                                .time_format
+                                .format_with_resolution(tick.ns, axis.major_ns),
+                            position: Point::new(x, 4.0),
+                            color: self.palette.text,
+                            size: 14.0,
                             font: Font::Default,
                             horizontal_alignment: alignment::Horizontal::Center,
                             vertical_alignment: alignment::Vertical::Top,
                         });
+                    }
+                }
+            });
+
+            // Markers are drawn across the ruler, so they stay visible at any scroll position.
+            let markers = self.marker_cache.draw(size, |frame| {
+                frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
+
+                for marker in &self.markers {
+                    let start = self.ns_to_x(marker.start);
+                    let end = self.ns_to_x(marker.end);
+                    if end < 0.0 || start > width {
+                        continue;
+                    }
+
+                    let max_width = if marker.is_bookmark() {
+                        frame.stroke(
+                            &Path::line(Point::new(start, 0.0), Point::new(start, size.height)),
+                            Stroke::default()
+                                .with_color(self.palette.marker)
+                                .with_width(1.5),
+                        );
+                        Self::MARKER_LABEL_WIDTH
+                    } else {
+                        let (start, end) = (start.max(0.0), end.min(width));
+                        frame.fill_rectangle(
+                            Point::new(start, Self::RULER_HEIGHT),
+                            Size::new(end - start, size.height - Self::RULER_HEIGHT),
+                            Color {
+                                a: 0.15,
+                                ..self.palette.marker
+                            },
+                        );
+                        end - start - 6.0
+                    };
+                    if let Some(label) = elide(&marker.text, max_width, 14.0) {
+                        frame.fill_text(Text {
+                            content: label,
+                            position: Point::new(start.max(0.0) + 3.0, Self::RULER_HEIGHT + 2.0),
+                            color: self.palette.marker,
+                            size: 14.0,
+                            font: Font::Default,
+                            horizontal_alignment: alignment::Horizontal::Left,
+                            vertical_alignment: alignment::Vertical::Top,
+                        });
+                    }
+                }
+            });
 
-                        x += spacing;
+            if self.is_grid_enabled {
+                let grid = self.grid_cache.draw(bounds.size(), |frame| {
+                    frame.translate(Vector::new(Self::GUTTER_WIDTH, 0.0));
+
+                    for tick in axis.ticks(logical_start..logical_end) {
+                        let x = self.ns_to_x(tick.ns);
+                        let color = if tick.major {
+                            self.palette.grid
+                        } else {
+                            Color {
+                                a: 0.25,
+                                ..self.palette.grid
+                            }
+                        };
+                        frame.stroke(
+                            &Path::line(
+                                Point::new(x, Self::RULER_HEIGHT),
+                                Point::new(x, size.height),
+                            ),
+                            Stroke::default().with_color(color),
+                        );
                     }
                 });
-                vec![grid, bar, overlay]
+                vec![grid, bar, chain, gutter, ruler, markers, overlay]
             } else {
-                vec![bar, overlay]
+                vec![bar, chain, gutter, ruler, markers, overlay]
             }
         }
 
         fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
             match self.interaction {
                 Interaction::Panning { .. } => mouse::Interaction::Grabbing,
+                Interaction::Selecting { .. } => mouse::Interaction::Crosshair,
                 Interaction::None if cursor.is_over(&bounds) => mouse::Interaction::Crosshair,
                 _ => mouse::Interaction::default(),
             }
@@ -525,52 +1969,121 @@ mod grid {
     }
 }
 
-fn to_si_time(nanoseconds: usize) -> String {
-    const NAMES: &[&'static str] = &["ns", "us", "ms", "s"];
+/// The average width of a character of the default font relative to the font size.
+///
+/// The canvas cannot measure text, so this is used to estimate the width of labels.
+const CHAR_WIDTH_RATIO: f32 = 0.6;
+
+/// Estimates the width of the given text in px.
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * CHAR_WIDTH_RATIO
+}
 
-    let levels = if nanoseconds == 0 {
-        0f32
+/// Shortens the text to fit into the given width in px, ending it with an ellipsis if it had to
+/// be truncated. Returns `None` if not even a single character fits.
+fn elide(text: &str, max_width: f32, size: f32) -> Option<String> {
+    let fitting = (max_width / (size * CHAR_WIDTH_RATIO)).max(0.0) as usize;
+    if text.chars().count() <= fitting {
+        Some(text.to_string())
+    } else if fitting >= 2 {
+        Some(
+            text.chars()
+                .take(fitting - 1)
+                .chain(std::iter::once('…'))
+                .collect(),
+        )
     } else {
-        ((nanoseconds as f32).ln() / 10f32.ln() + 1e-15).floor()
-    };
-    let mut display_ns = nanoseconds as f32;
-    while display_ns >= 1000.0 {
-        display_ns /= 1000.0;
+        None
     }
-
-    format!("{}{}", display_ns.round(), NAMES[(levels / 3.0) as usize])
 }
 
 enum Interaction {
     None,
-    Panning { start: Point },
+    Panning {
+        start: Point,
+    },
+    /// Dragging out the range of an annotation, or clicking to add a bookmark.
+    Selecting {
+        origin: Point,
+    },
 }
 
 #[derive(Default)]
 struct Controls {
     toggle_button: button::State,
     reset_button: button::State,
+    theme_list: pick_list::State<Theme>,
+    export_path: text_input::State,
+    export_width: text_input::State,
+    export_button: button::State,
+    session_path: text_input::State,
+    save_session_button: button::State,
+    open_session_button: button::State,
+    compare_before: text_input::State,
+    compare_after: text_input::State,
+    align_on_list: pick_list::State<AlignOn>,
+    align_task: text_input::State,
+    align_activation: text_input::State,
+    align_offset: text_input::State,
+    compare_button: button::State,
+    close_comparison_button: button::State,
+    chain_tasks: text_input::State,
+    show_chain_button: button::State,
+    clear_chain_button: button::State,
+    lane_order_list: pick_list::State<LaneOrder>,
+    app_path: text_input::State,
+    load_priorities_button: button::State,
+}
+
+/// The state of the timeline and its settings which the controls show.
+struct ControlsState<'a> {
+    is_playing: bool,
+    is_grid_enabled: bool,
+    is_lane_info_enabled: bool,
+    theme: Theme,
+    status: &'a str,
+    export: &'a Export,
+    session: &'a SessionFile,
+    compare: &'a CompareSettings,
+    is_comparing: bool,
+    chain: &'a ChainSettings,
+    time_format: TimeFormat,
+    lane_order: LaneOrder,
+    app: &'a AppDescription,
 }
 
 impl Controls {
-    fn view<'a>(
-        &'a mut self,
-        is_playing: bool,
-        is_grid_enabled: bool,
-        status: impl AsRef<str>,
-    ) -> Element<'a, Message> {
-        let playback_controls = Row::new().spacing(10).push(Button::new(
-            &mut self.toggle_button,
-            Text::new(if is_playing { "Pause" } else { "Play" }),
-        ));
+    fn view<'a>(&'a mut self, state: ControlsState<'_>) -> Element<'a, Message> {
+        let ControlsState {
+            is_playing,
+            is_grid_enabled,
+            is_lane_info_enabled,
+            theme,
+            status,
+            export,
+            session,
+            compare,
+            is_comparing,
+            chain,
+            time_format,
+            lane_order,
+            app,
+        } = state;
+        let playback_controls = Row::new().spacing(10).push(
+            Button::new(
+                &mut self.toggle_button,
+                Text::new(if is_playing { "Pause" } else { "Play" }),
+            )
+            .style(style::Button(theme)),
+        );
 
         let speed_controls = Row::new()
-            .push(Text::new(status.as_ref()))
+            .push(Text::new(status))
             .width(Length::Fill)
             .align_items(Alignment::Center)
             .spacing(10);
 
-        Row::new()
+        let controls = Row::new()
             .padding(10)
             .spacing(20)
             .align_items(Alignment::Center)
@@ -582,51 +2095,378 @@ impl Controls {
                     .spacing(5)
                     .text_size(16),
             )
-            .push(Button::new(&mut self.reset_button, Text::new("Reset")).on_press(Message::Reset))
-            .into()
+            .push(
+                Tooltip::new(
+                    Checkbox::new(is_lane_info_enabled, "Lane info", Message::ToggleLaneInfo)
+                        .size(16)
+                        .spacing(5)
+                        .text_size(16),
+                    "Show the priority and activation count of each task",
+                    tooltip::Position::Top,
+                )
+                .gap(5)
+                .padding(8)
+                .style(style::Tooltip(theme)),
+            )
+            .push(
+                PickList::new(
+                    &mut self.theme_list,
+                    &Theme::ALL[..],
+                    Some(theme),
+                    Message::ThemeSelected,
+                )
+                .text_size(16),
+            )
+            .push(
+                Button::new(&mut self.reset_button, Text::new("Reset"))
+                    .on_press(Message::Reset)
+                    .style(style::Button(theme)),
+            );
+
+        let export_controls = Row::new()
+            .padding(10)
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                TextInput::new(
+                    &mut self.export_path,
+                    "Path of a .svg, .png, .json, .vcd or .csv file",
+                    &export.path,
+                    Message::ExportPathChanged,
+                )
+                .on_submit(Message::Export)
+                .padding(5)
+                .size(16)
+                .width(Length::Fill)
+                .style(style::TextInput(theme)),
+            )
+            .push(
+                Tooltip::new(
+                    TextInput::new(
+                        &mut self.export_width,
+                        "Width",
+                        &export.width,
+                        Message::ExportWidthChanged,
+                    )
+                    .on_submit(Message::Export)
+                    .padding(5)
+                    .size(16)
+                    .width(Length::Units(60))
+                    .style(style::TextInput(theme)),
+                    "The width of the exported timeline in px",
+                    tooltip::Position::Top,
+                )
+                .gap(5)
+                .padding(8)
+                .style(style::Tooltip(theme)),
+            )
+            .push(
+                Checkbox::new(
+                    export.is_full_trace,
+                    "Full trace",
+                    Message::ToggleExportFullTrace,
+                )
+                .size(16)
+                .spacing(5)
+                .text_size(16),
+            )
+            .push(
+                Button::new(&mut self.export_button, Text::new("Export"))
+                    .on_press(Message::Export)
+                    .style(style::Button(theme)),
+            );
+
+        let session_controls = Row::new()
+            .padding(10)
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                TextInput::new(
+                    &mut self.session_path,
+                    "Path of a session file",
+                    &session.path,
+                    Message::SessionPathChanged,
+                )
+                .padding(5)
+                .size(16)
+                .width(Length::Fill)
+                .style(style::TextInput(theme)),
+            )
+            .push(
+                Tooltip::new(
+                    Button::new(&mut self.save_session_button, Text::new("Save session"))
+                        .on_press(Message::SaveSession)
+                        .style(style::Button(theme)),
+                    "Save the trace and the view to reopen them later",
+                    tooltip::Position::Top,
+                )
+                .gap(5)
+                .padding(8)
+                .style(style::Tooltip(theme)),
+            )
+            .push(
+                Button::new(&mut self.open_session_button, Text::new("Open session"))
+                    .on_press(Message::OpenSession)
+                    .style(style::Button(theme)),
+            );
+
+        let compare_input = |state: &'a mut text_input::State,
+                             placeholder: &str,
+                             value: &str,
+                             on_change: fn(String) -> Message,
+                             width: Length| {
+            TextInput::new(state, placeholder, value, on_change)
+                .on_submit(Message::Compare)
+                .padding(5)
+                .size(16)
+                .width(width)
+                .style(style::TextInput(theme))
+        };
+        let mut compare_controls = Row::new()
+            .padding(10)
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(compare_input(
+                &mut self.compare_before,
+                "Trace before, or empty for the current trace",
+                &compare.before,
+                Message::CompareBeforeChanged,
+                Length::Fill,
+            ))
+            .push(compare_input(
+                &mut self.compare_after,
+                "Trace after",
+                &compare.after,
+                Message::CompareAfterChanged,
+                Length::Fill,
+            ))
+            .push(
+                PickList::new(
+                    &mut self.align_on_list,
+                    &AlignOn::ALL[..],
+                    Some(compare.align_on),
+                    Message::AlignOnSelected,
+                )
+                .text_size(16),
+            );
+        compare_controls = match compare.align_on {
+            AlignOn::Start => compare_controls,
+            AlignOn::Activation => compare_controls
+                .push(compare_input(
+                    &mut self.align_task,
+                    "Task",
+                    &compare.task,
+                    Message::AlignTaskChanged,
+                    Length::Units(160),
+                ))
+                .push(
+                    Tooltip::new(
+                        compare_input(
+                            &mut self.align_activation,
+                            "Nr.",
+                            &compare.activation,
+                            Message::AlignActivationChanged,
+                            Length::Units(60),
+                        ),
+                        "The number of the activation of the task to align on, from 1",
+                        tooltip::Position::Top,
+                    )
+                    .gap(5)
+                    .padding(8)
+                    .style(style::Tooltip(theme)),
+                ),
+            AlignOn::Manual => compare_controls.push(
+                Tooltip::new(
+                    compare_input(
+                        &mut self.align_offset,
+                        "Offset",
+                        &compare.offset,
+                        Message::AlignOffsetChanged,
+                        Length::Units(120),
+                    ),
+                    "The ns added to the timestamps of the trace after",
+                    tooltip::Position::Top,
+                )
+                .gap(5)
+                .padding(8)
+                .style(style::Tooltip(theme)),
+            ),
+        };
+        compare_controls = compare_controls.push(
+            Button::new(&mut self.compare_button, Text::new("Compare"))
+                .on_press(Message::Compare)
+                .style(style::Button(theme)),
+        );
+        if is_comparing {
+            compare_controls = compare_controls.push(
+                Button::new(&mut self.close_comparison_button, Text::new("Close"))
+                    .on_press(Message::CloseComparison)
+                    .style(style::Button(theme)),
+            );
+        }
+
+        let mut chain_controls = Row::new()
+            .padding(10)
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                TextInput::new(
+                    &mut self.chain_tasks,
+                    "Chain of tasks, e.g. UART0 -> parse -> send_reply",
+                    &chain.tasks,
+                    Message::ChainChanged,
+                )
+                .on_submit(Message::ShowChain)
+                .padding(5)
+                .size(16)
+                .width(Length::Fill)
+                .style(style::TextInput(theme)),
+            );
+        if let Some(latency) = &chain.latency {
+            let time = |ns: Option<usize>| {
+                ns.map(|ns| time_format.format(ns as u64))
+                    .unwrap_or_else(|| String::from("-"))
+            };
+            chain_controls = chain_controls.push(Text::new(format!(
+                "Latency of {} instances: min {}, median {}, p90 {}, p99 {}, max {}",
+                latency.instances,
+                time(latency.min_ns),
+                time(latency.median_ns),
+                time(latency.p90_ns),
+                time(latency.p99_ns),
+                time(latency.max_ns)
+            )));
+        }
+        chain_controls = chain_controls.push(
+            Tooltip::new(
+                Button::new(&mut self.show_chain_button, Text::new("Show chain"))
+                    .on_press(Message::ShowChain)
+                    .style(style::Button(theme)),
+                "Link the activations which caused each other and measure the end-to-end latency",
+                tooltip::Position::Top,
+            )
+            .gap(5)
+            .padding(8)
+            .style(style::Tooltip(theme)),
+        );
+        if chain.latency.is_some() {
+            chain_controls = chain_controls.push(
+                Button::new(&mut self.clear_chain_button, Text::new("Clear"))
+                    .on_press(Message::ClearChain)
+                    .style(style::Button(theme)),
+            );
+        }
+
+        let layout_controls = Row::new()
+            .padding(10)
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                PickList::new(
+                    &mut self.lane_order_list,
+                    &LaneOrder::ALL[..],
+                    Some(lane_order),
+                    Message::LaneOrderSelected,
+                )
+                .text_size(16),
+            )
+            .push(
+                TextInput::new(
+                    &mut self.app_path,
+                    "Source of the RTIC application, or a .json map of task priorities",
+                    &app.path,
+                    Message::AppPathChanged,
+                )
+                .on_submit(Message::LoadPriorities)
+                .padding(5)
+                .size(16)
+                .width(Length::Fill)
+                .style(style::TextInput(theme)),
+            )
+            .push(
+                Tooltip::new(
+                    Button::new(
+                        &mut self.load_priorities_button,
+                        Text::new("Load priorities"),
+                    )
+                    .on_press(Message::LoadPriorities)
+                    .style(style::Button(theme)),
+                    "Read the priorities of the tasks, to order the lanes by them",
+                    tooltip::Position::Top,
+                )
+                .gap(5)
+                .padding(8)
+                .style(style::Tooltip(theme)),
+            );
+
+        Container::new(
+            Column::new()
+                .push(controls)
+                .push(export_controls)
+                .push(session_controls)
+                .push(compare_controls)
+                .push(chain_controls)
+                .push(layout_controls),
+        )
+        .width(Length::Fill)
+        .style(style::Controls(theme))
+        .into()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct EventStyle {
-    paint: Paint,
+/// Returns a color for the given task which only depends on its name.
+///
+/// The name is hashed with FNV-1a so the color is the same across sessions and platforms, and
+/// the hash is mapped onto a hue so any number of tasks gets a color.
+fn task_color(name: &str) -> Color {
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+
+    let hue = (hash % 360) as f32;
+    let saturation = 0.55 + ((hash >> 16) % 25) as f32 / 100.0;
+    let lightness = 0.45 + ((hash >> 32) % 20) as f32 / 100.0;
+    hsl_to_color(hue, saturation, lightness)
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Paint {
-    color: Color,
+fn hsl_to_color(hue: f32, saturation: f32, lightness: f32) -> Color {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    Color::from_rgb(r + m, g + m, b + m)
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-struct Bar {
-    start_ns: usize,
-    end_ns: Option<usize>,
-    isr: String,
-    channel: usize,
+/// Parses a color in the `#rrggbb` format.
+fn parse_color(color: &str) -> Option<Color> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
 }
 
-fn _px_to_ns(px: f32, zoom: f32) -> f32 {
-    px / (1e3 * zoom)
+/// Formats a color in the `#rrggbb` format of [`parse_color`].
+fn format_color(color: Color) -> String {
+    let [r, g, b] = [color.r, color.g, color.b].map(|c| (c * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
 }
 
-fn _ns_to_px(ns: f32, zoom: f32) -> f32 {
-    ns * (1e3 * zoom)
+#[derive(Debug, Clone, Copy)]
+struct EventStyle {
+    paint: Paint,
 }
 
-mod style {
-    use iced::container;
-    use iced::Color;
-
-    pub struct Tooltip;
-
-    impl container::StyleSheet for Tooltip {
-        fn style(&self) -> container::Style {
-            container::Style {
-                text_color: Some(Color::from_rgb8(0xEE, 0xEE, 0xEE)),
-                background: Some(Color::from_rgb(0.11, 0.42, 0.87).into()),
-                border_radius: 12.0,
-                ..container::Style::default()
-            }
-        }
-    }
+#[derive(Debug, Clone, Copy)]
+struct Paint {
+    color: Color,
 }
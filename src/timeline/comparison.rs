@@ -0,0 +1,149 @@
+use std::fmt;
+
+use iced::{
+    scrollable::{self, Scrollable},
+    Column, Container, Element, Length, Row, Text,
+};
+use rtic_scope_frontend_racer::{compare::TaskDelta, time::TimeFormat};
+
+use super::grid::{self, Grid};
+use crate::style::{self, Theme};
+
+/// The event which both traces are lined up on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlignOn {
+    #[default]
+    Start,
+    Activation,
+    Manual,
+}
+
+impl AlignOn {
+    pub const ALL: [AlignOn; 3] = [AlignOn::Start, AlignOn::Activation, AlignOn::Manual];
+}
+
+impl fmt::Display for AlignOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignOn::Start => write!(f, "Align on start"),
+            AlignOn::Activation => write!(f, "Align on activation"),
+            AlignOn::Manual => write!(f, "Align manually"),
+        }
+    }
+}
+
+/// A second trace which is shown below the first one with the same zoom and pan, shifted by an
+/// offset, together with the differences of the statistics of all tasks.
+pub struct Comparison {
+    pub(super) grid: Grid,
+    /// The path the second trace was read from.
+    path: String,
+    /// The ns which are added to the timestamps of the second trace to line it up with the first.
+    pub(super) offset: i64,
+    deltas: Vec<TaskDelta>,
+    scroll: scrollable::State,
+}
+
+impl Comparison {
+    const TABLE_HEIGHT: u16 = 160;
+    const NAME_WIDTH: u16 = 200;
+    const COLUMN_WIDTH: u16 = 110;
+
+    pub fn new(grid: Grid, path: String, offset: i64, deltas: Vec<TaskDelta>) -> Self {
+        Self {
+            grid,
+            path,
+            offset,
+            deltas,
+            scroll: scrollable::State::default(),
+        }
+    }
+
+    pub fn view<'a>(
+        &'a mut self,
+        theme: Theme,
+        time_format: TimeFormat,
+    ) -> Element<'a, grid::Message> {
+        let header = Text::new(format!(
+            "{}, shifted by {}",
+            self.path,
+            signed(self.offset as f64, time_format)
+        ))
+        .size(14);
+
+        let cell =
+            |content: String, width: u16| Text::new(content).size(14).width(Length::Units(width));
+        let row = |cells: [String; 10]| {
+            let [name, cells @ ..] = cells;
+            cells.into_iter().fold(
+                Row::new().spacing(5).push(cell(name, Self::NAME_WIDTH)),
+                |row, content| row.push(cell(content, Self::COLUMN_WIDTH)),
+            )
+        };
+
+        let mut table = Scrollable::new(&mut self.scroll)
+            .padding(10)
+            .spacing(2)
+            .width(Length::Fill)
+            .height(Length::Units(Self::TABLE_HEIGHT))
+            .push(row([
+                "Task",
+                "Count before",
+                "Count after",
+                "Δ count",
+                "Mean before",
+                "Mean after",
+                "Δ mean",
+                "Max before",
+                "Max after",
+                "Δ max",
+            ]
+            .map(String::from)));
+        let duration = |ns: Option<f64>| {
+            ns.map(|ns| time_format.format(ns.round() as u64))
+                .unwrap_or_default()
+        };
+        for delta in &self.deltas {
+            table = table.push(row([
+                delta.name.clone(),
+                delta.base.completed.to_string(),
+                delta.other.completed.to_string(),
+                format!("{:+}", delta.completed()),
+                duration(delta.base.mean_ns()),
+                duration(delta.other.mean_ns()),
+                delta
+                    .mean_ns()
+                    .map(|ns| signed(ns, time_format))
+                    .unwrap_or_default(),
+                duration(delta.base.max_ns.map(|ns| ns as f64)),
+                duration(delta.other.max_ns.map(|ns| ns as f64)),
+                delta
+                    .max_ns()
+                    .map(|ns| signed(ns as f64, time_format))
+                    .unwrap_or_default(),
+            ]));
+        }
+
+        Column::new()
+            .height(Length::Fill)
+            .push(
+                Container::new(header)
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(style::Controls(theme)),
+            )
+            .push(self.grid.view())
+            .push(
+                Container::new(table)
+                    .width(Length::Fill)
+                    .style(style::Controls(theme)),
+            )
+            .into()
+    }
+}
+
+/// Formats a difference of durations with its sign.
+fn signed(ns: f64, time_format: TimeFormat) -> String {
+    let sign = if ns < 0.0 { '-' } else { '+' };
+    format!("{}{}", sign, time_format.format(ns.abs().round() as u64))
+}
@@ -0,0 +1,233 @@
+use iced::{
+    alignment,
+    canvas::event::{self, Event},
+    canvas::{self, Cache, Canvas, Cursor, Frame, Geometry, Path, Stroke, Text},
+    mouse, Color, Element, Font, Length, Point, Rectangle, Size,
+};
+
+use crate::style::{Palette, Theme};
+
+/// A compressed overview of the whole trace with the currently visible range highlighted.
+pub struct Minimap {
+    density_cache: Cache,
+    /// The fraction of each bin that is covered by a bar, per lane.
+    density: Vec<Vec<f32>>,
+    colors: Vec<Color>,
+    /// The range of the whole trace in ns.
+    range: (u64, u64),
+    /// The range that is currently visible in the grid in ns.
+    view: (f64, f64),
+    /// The distance between the cursor and the center of the view while dragging, in ns.
+    grab_offset: Option<f64>,
+    palette: Palette,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Center the grid on the given timestamp in ns.
+    Navigate(f64),
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Minimap {
+    /// The number of bins the trace is split into per lane.
+    pub const BINS: usize = 1024;
+    const HEIGHT: u16 = 60;
+    const LABEL_WIDTH: f32 = 160.0;
+
+    pub fn new() -> Self {
+        Self {
+            density_cache: Cache::default(),
+            density: vec![],
+            colors: vec![],
+            range: (0, 0),
+            view: (0.0, 0.0),
+            grab_offset: None,
+            palette: Theme::default().palette(),
+        }
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(Self::HEIGHT))
+            .into()
+    }
+
+    pub(crate) fn set_density(&mut self, density: Vec<Vec<f32>>, colors: Vec<Color>) {
+        self.density = density;
+        self.colors = colors;
+        self.density_cache.clear();
+    }
+
+    pub(crate) fn set_range(&mut self, start: u64, end: u64) {
+        self.range = (start, end);
+        self.density_cache.clear();
+    }
+
+    pub(crate) fn set_view(&mut self, start: f64, end: f64) {
+        self.view = (start, end);
+    }
+
+    pub(crate) fn set_theme(&mut self, theme: Theme) {
+        self.palette = theme.palette();
+        self.density_cache.clear();
+    }
+
+    /// Returns the span of the trace in ns, which is at least 1.
+    fn span(&self) -> f64 {
+        self.range.1.saturating_sub(self.range.0).max(1) as f64
+    }
+
+    fn ns_to_x(&self, ns: f64, width: f32) -> f32 {
+        // Timestamps far into a trace lose precision in f32, so only the px are converted to it.
+        let fraction = (ns - self.range.0 as f64) / self.span();
+        Self::LABEL_WIDTH + (fraction * width as f64) as f32
+    }
+
+    fn x_to_ns(&self, x: f32, width: f32) -> f64 {
+        let fraction = (x - Self::LABEL_WIDTH) as f64 / width.max(1.0) as f64;
+        self.range.0 as f64 + fraction * self.span()
+    }
+}
+
+impl canvas::Program<Message> for Minimap {
+    fn update(
+        &mut self,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let width = (bounds.width - Self::LABEL_WIDTH).max(0.0);
+        let cursor_x = match cursor.position() {
+            Some(position) => position.x - bounds.x,
+            None => return (event::Status::Ignored, None),
+        };
+        let cursor_ns = self.x_to_ns(cursor_x, width);
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if cursor.is_over(&bounds) && cursor_x >= Self::LABEL_WIDTH =>
+            {
+                // Keep the grabbed point under the cursor when dragging the viewport rectangle,
+                // otherwise jump to the clicked position.
+                let center = (self.view.0 + self.view.1) / 2.0;
+                let offset = if self.view.0 <= cursor_ns && cursor_ns <= self.view.1 {
+                    cursor_ns - center
+                } else {
+                    0.0
+                };
+                self.grab_offset = Some(offset);
+                (
+                    event::Status::Captured,
+                    Some(Message::Navigate(cursor_ns - offset)),
+                )
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => match self.grab_offset {
+                Some(offset) => (
+                    event::Status::Captured,
+                    Some(Message::Navigate(cursor_ns - offset)),
+                ),
+                None => (event::Status::Ignored, None),
+            },
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let status = if self.grab_offset.take().is_some() {
+                    event::Status::Captured
+                } else {
+                    event::Status::Ignored
+                };
+                (status, None)
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let size = bounds.size();
+        let width = (size.width - Self::LABEL_WIDTH).max(0.0);
+
+        let density = self.density_cache.draw(size, |frame| {
+            frame.fill_rectangle(Point::ORIGIN, size, self.palette.gutter);
+            frame.fill_text(Text {
+                content: "Overview".to_string(),
+                position: Point::new(4.0, size.height / 2.0),
+                color: self.palette.text,
+                size: 15.0,
+                font: Font::Default,
+                horizontal_alignment: alignment::Horizontal::Left,
+                vertical_alignment: alignment::Vertical::Center,
+            });
+
+            if self.density.is_empty() {
+                return;
+            }
+
+            let lane_height = size.height / self.density.len() as f32;
+            let bin_width = width / Self::BINS as f32;
+            for (lane, (bins, color)) in self.density.iter().zip(&self.colors).enumerate() {
+                let y = lane as f32 * lane_height;
+                for (bin, &coverage) in bins.iter().enumerate() {
+                    if coverage <= 0.0 {
+                        continue;
+                    }
+
+                    // Scale the opacity with the coverage, but keep short activations visible.
+                    let color = Color {
+                        a: 0.3 + 0.7 * coverage.min(1.0),
+                        ..*color
+                    };
+                    frame.fill_rectangle(
+                        Point::new(Self::LABEL_WIDTH + bin as f32 * bin_width, y),
+                        Size::new(bin_width.max(1.0), lane_height.max(1.0)),
+                        color,
+                    );
+                }
+            }
+        });
+
+        let viewport = {
+            let mut frame = Frame::new(size);
+            let start = self
+                .ns_to_x(self.view.0, width)
+                .max(Self::LABEL_WIDTH)
+                .min(size.width);
+            let end = self
+                .ns_to_x(self.view.1, width)
+                .max(Self::LABEL_WIDTH)
+                .min(size.width);
+            let viewport = Path::rectangle(
+                Point::new(start, 0.0),
+                Size::new((end - start).max(2.0), size.height),
+            );
+            frame.fill(
+                &viewport,
+                Color {
+                    a: 0.15,
+                    ..self.palette.text
+                },
+            );
+            frame.stroke(
+                &viewport,
+                Stroke::default()
+                    .with_color(self.palette.text)
+                    .with_width(1.5),
+            );
+            frame.into_geometry()
+        };
+
+        vec![density, viewport]
+    }
+
+    fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
+        match self.grab_offset {
+            Some(_) => mouse::Interaction::Grabbing,
+            None if cursor.is_over(&bounds) => mouse::Interaction::Grab,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use iced::{
+    button::{self, Button},
+    scrollable::{self, Scrollable},
+    text_input::{self, TextInput},
+    Alignment, Column, Container, Element, Length, Row, Text,
+};
+use rtic_scope_frontend_racer::time::TimeFormat;
+
+use crate::{
+    session::Marker,
+    style::{self, Theme},
+};
+
+/// The list of all bookmarks and annotations next to the timeline, for quick navigation.
+#[derive(Default)]
+pub struct Sidebar {
+    scroll: scrollable::State,
+    /// The state of the widgets of each marker.
+    entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+struct Entry {
+    text: text_input::State,
+    go_button: button::State,
+    delete_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    TextChanged(usize, String),
+    /// Show the marker with the given index in the grid.
+    GoTo(usize),
+    Delete(usize),
+}
+
+impl Sidebar {
+    const WIDTH: u16 = 240;
+
+    pub fn view<'a>(
+        &'a mut self,
+        markers: &[Marker],
+        theme: Theme,
+        time_format: TimeFormat,
+    ) -> Element<'a, Message> {
+        self.entries.resize_with(markers.len(), Entry::default);
+
+        let mut list = Scrollable::new(&mut self.scroll)
+            .padding(10)
+            .spacing(15)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        if markers.is_empty() {
+            list = list.push(
+                Text::new("Click the timeline to add a bookmark, or drag to annotate a range.")
+                    .size(14)
+                    .color(theme.palette().dimmed_text),
+            );
+        }
+
+        for (index, (marker, entry)) in markers.iter().zip(&mut self.entries).enumerate() {
+            let time = if marker.is_bookmark() {
+                time_format.format(marker.start)
+            } else {
+                format!(
+                    "{} - {}",
+                    time_format.format(marker.start),
+                    time_format.format(marker.end)
+                )
+            };
+
+            list = list.push(
+                Column::new()
+                    .spacing(5)
+                    .push(
+                        TextInput::new(&mut entry.text, "Text", &marker.text, move |text| {
+                            Message::TextChanged(index, text)
+                        })
+                        .padding(5)
+                        .size(16)
+                        .style(style::TextInput(theme)),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Alignment::Center)
+                            .push(Text::new(time).size(14).width(Length::Fill))
+                            .push(
+                                Button::new(&mut entry.go_button, Text::new("Go").size(14))
+                                    .on_press(Message::GoTo(index))
+                                    .style(style::Button(theme)),
+                            )
+                            .push(
+                                Button::new(&mut entry.delete_button, Text::new("Delete").size(14))
+                                    .on_press(Message::Delete(index))
+                                    .style(style::Button(theme)),
+                            ),
+                    ),
+            );
+        }
+
+        Container::new(list)
+            .width(Length::Units(Self::WIDTH))
+            .height(Length::Fill)
+            .style(style::Controls(theme))
+            .into()
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    io::{self, BufRead},
+    mem,
+    ops::Range,
+};
+
+use bio::data_structures::interval_tree::IntervalTree;
+use rtic_scope_api::{EventChunk, EventType, TaskAction};
+
+use crate::{
+    ingest::Ingest,
+    retention::{Retention, Spill, SpilledBar},
+    summary::Summary,
+};
+
+/// A completed activation of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bar {
+    pub start: usize,
+    pub end: usize,
+    /// The index of the task in [`TraceModel::lanes`].
+    pub channel: usize,
+}
+
+impl Bar {
+    pub fn duration(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// A task which appeared in the trace.
+#[derive(Debug, Clone)]
+pub struct Lane {
+    pub name: String,
+    /// The priority of the task, if known.
+    pub priority: Option<u8>,
+    /// The number of times the task was entered.
+    pub activations: usize,
+    /// The coverage of the lane at multiple resolutions.
+    pub summary: Summary,
+}
+
+/// Statistics over all completed activations of a task, including evicted ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    pub completed: usize,
+    pub total_ns: usize,
+    pub min_ns: Option<usize>,
+    pub max_ns: Option<usize>,
+    /// The start of the first completed activation.
+    pub first_start: Option<usize>,
+    /// The start of the last completed activation.
+    pub last_start: Option<usize>,
+}
+
+impl TaskStats {
+    pub fn mean_ns(&self) -> Option<f64> {
+        if self.completed == 0 {
+            None
+        } else {
+            Some(self.total_ns as f64 / self.completed as f64)
+        }
+    }
+
+    /// Returns the mean time between the starts of two consecutive activations.
+    pub fn mean_period_ns(&self) -> Option<f64> {
+        match (self.first_start, self.last_start) {
+            (Some(first), Some(last)) if self.completed > 1 => {
+                Some((last - first) as f64 / (self.completed - 1) as f64)
+            }
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, bar: &Bar) {
+        let duration = bar.duration();
+        self.completed += 1;
+        self.total_ns += duration;
+        self.min_ns = Some(self.min_ns.map_or(duration, |min| min.min(duration)));
+        self.max_ns = Some(self.max_ns.map_or(duration, |max| max.max(duration)));
+        self.first_start = Some(self.first_start.map_or(bar.start, |s| s.min(bar.start)));
+        self.last_start = Some(self.last_start.map_or(bar.start, |s| s.max(bar.start)));
+    }
+}
+
+/// The tasks and activations of a trace, independent of how they are displayed.
+///
+/// Events are fed in with [`TraceModel::ingest`] or [`TraceModel::add_event`]. Enter and exit
+/// events of a task are matched into [`Bar`]s, which can be queried by time range with
+/// [`TraceModel::bars`]. All timestamps are in ns since the start of the trace.
+pub struct TraceModel {
+    bars: IntervalTree<usize, Bar>,
+    bar_count: usize,
+    ingest: Ingest,
+    lanes: Vec<Lane>,
+    stats: Vec<TaskStats>,
+    range: Option<(usize, usize)>,
+    /// The timestamps of all overflow packets.
+    overflows: Vec<usize>,
+    unmatched_exits: usize,
+    unknown_events: usize,
+    retention: Retention,
+    spill: Option<Spill>,
+    spill_error: Option<io::Error>,
+    evicted_before: usize,
+}
+
+impl Default for TraceModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TraceModel {
+    /// The estimated memory used per bar in bytes, including the interval tree.
+    const BAR_SIZE: usize = mem::size_of::<Bar>() + 48;
+
+    pub fn new() -> Self {
+        Self {
+            bars: IntervalTree::new(),
+            bar_count: 0,
+            ingest: Ingest::new(),
+            lanes: vec![],
+            stats: vec![],
+            range: None,
+            overflows: vec![],
+            unmatched_exits: 0,
+            unknown_events: 0,
+            retention: Retention::default(),
+            spill: None,
+            spill_error: None,
+            evicted_before: 0,
+        }
+    }
+
+    /// Removes all tasks and bars, keeping the retention limits.
+    pub fn clear(&mut self) {
+        let retention = mem::take(&mut self.retention);
+        *self = Self::new();
+        if let Err(e) = self.set_retention(retention) {
+            self.spill_error = Some(e);
+        }
+    }
+
+    /// Reads a trace which was recorded as one JSON-serialized `EventChunk` per line, which is
+    /// also how they are sent to the frontend socket.
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut model = Self::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chunk: EventChunk = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid event chunk on line {}: {}", number + 1, e),
+                )
+            })?;
+            model.ingest(&chunk);
+        }
+        Ok(model)
+    }
+
+    /// Adds all events of the chunk.
+    pub fn ingest(&mut self, chunk: &EventChunk) {
+        let timestamp = chunk.timestamp.offset.as_nanos() as usize;
+        for event in &chunk.events {
+            self.add_event(timestamp, event.clone());
+        }
+    }
+
+    /// Adds a single event which happened at the given timestamp.
+    pub fn add_event(&mut self, timestamp: usize, event: EventType) {
+        self.range = Some(match self.range {
+            Some((min, max)) => (min.min(timestamp), max.max(timestamp)),
+            None => (timestamp, timestamp),
+        });
+
+        match event {
+            EventType::Overflow => self.overflows.push(timestamp),
+            EventType::Task { name, action } => match action {
+                TaskAction::Entered => {
+                    let channel = self.ingest.enter(&name, timestamp);
+                    if channel == self.lanes.len() {
+                        self.lanes.push(Lane {
+                            name,
+                            priority: None,
+                            activations: 0,
+                            summary: Summary::new(),
+                        });
+                        self.stats.push(TaskStats::default());
+                    }
+                    self.lanes[channel].activations += 1;
+                }
+                TaskAction::Exited => match self.ingest.exit(&name, timestamp) {
+                    Some(activation) => self.insert(Bar {
+                        start: activation.start,
+                        end: activation.end,
+                        channel: activation.channel,
+                    }),
+                    None => self.unmatched_exits += 1,
+                },
+                TaskAction::Returned => (),
+            },
+            EventType::Unknown(_) | EventType::Unmappable(_, _) | EventType::Invalid(_) => {
+                self.unknown_events += 1
+            }
+        }
+    }
+
+    fn insert(&mut self, bar: Bar) {
+        self.lanes[bar.channel]
+            .summary
+            .insert(bar.start as u64, bar.end as u64);
+        self.stats[bar.channel].add(&bar);
+        self.bars.insert(bar.start..bar.end, bar);
+        self.bar_count += 1;
+        self.enforce_retention();
+    }
+
+    /// Returns all tasks, indexed by their channel.
+    pub fn lanes(&self) -> &[Lane] {
+        &self.lanes
+    }
+
+    /// Returns the channel of the task with the given name.
+    pub fn channel(&self, name: &str) -> Option<usize> {
+        self.ingest.get(name)
+    }
+
+    pub fn set_priority(&mut self, channel: usize, priority: Option<u8>) {
+        self.lanes[channel].priority = priority;
+    }
+
+    /// Returns the statistics of all tasks, indexed by their channel.
+    pub fn stats(&self) -> &[TaskStats] {
+        &self.stats
+    }
+
+    /// Returns the bars in memory which intersect the given range.
+    pub fn bars(&self, range: Range<usize>) -> impl Iterator<Item = &Bar> + '_ {
+        let range = range.start..range.end.max(range.start);
+        self.bars.find(range).map(|entry| entry.data())
+    }
+
+    /// Returns all bars in memory sorted by their start.
+    pub fn sorted_bars(&self) -> Vec<Bar> {
+        let mut bars = self.bars(0..usize::MAX).copied().collect::<Vec<_>>();
+        bars.sort_by_key(|bar| (bar.start, bar.end, bar.channel));
+        bars
+    }
+
+    /// Returns all bars sorted by their start, including the ones which were spilled to disk.
+    pub fn all_bars(&mut self) -> io::Result<Vec<Bar>> {
+        let mut bars = self.read_spilled(0..usize::MAX)?;
+        bars.extend(self.bars(0..usize::MAX).copied());
+        bars.sort_by_key(|bar| (bar.start, bar.end, bar.channel));
+        Ok(bars)
+    }
+
+    /// Returns the events which rebuild the tasks, bars, open activations and overflows of the
+    /// trace when added to an empty model in order.
+    ///
+    /// Unmatched exits and unknown events are only counted, so they are not part of it.
+    pub fn events(&mut self) -> io::Result<Vec<(usize, EventType)>> {
+        let bars = self.all_bars()?;
+        let task = |channel: usize, action| EventType::Task {
+            name: self.lanes[channel].name.clone(),
+            action,
+        };
+
+        // Exits come first at the same timestamp, so back-to-back activations stay separate.
+        let mut events = vec![];
+        for bar in bars {
+            events.push((
+                bar.start,
+                1,
+                bar.channel,
+                task(bar.channel, TaskAction::Entered),
+            ));
+            events.push((
+                bar.end,
+                0,
+                bar.channel,
+                task(bar.channel, TaskAction::Exited),
+            ));
+        }
+        for (channel, start) in self.ingest.open() {
+            events.push((start, 1, channel, task(channel, TaskAction::Entered)));
+        }
+        for &timestamp in &self.overflows {
+            events.push((timestamp, 2, 0, EventType::Overflow));
+        }
+        events.sort_by_key(|&(timestamp, order, channel, _)| (timestamp, order, channel));
+
+        Ok(events
+            .into_iter()
+            .map(|(timestamp, _, _, event)| (timestamp, event))
+            .collect())
+    }
+
+    /// Returns the number of bars in memory.
+    pub fn bar_count(&self) -> usize {
+        self.bar_count
+    }
+
+    /// Returns the first and last timestamp of the trace.
+    pub fn range(&self) -> Option<(usize, usize)> {
+        self.range
+    }
+
+    /// Returns the number of overflow packets, after which events may be missing.
+    pub fn overflows(&self) -> usize {
+        self.overflows.len()
+    }
+
+    /// Returns the timestamps of all overflow packets in the order they were received.
+    pub fn overflow_timestamps(&self) -> &[usize] {
+        &self.overflows
+    }
+
+    /// Returns the number of exits without a preceding enter of the same task.
+    pub fn unmatched_exits(&self) -> usize {
+        self.unmatched_exits
+    }
+
+    /// Returns the number of enters which were not followed by an exit of the same task (yet).
+    pub fn open_activations(&self) -> usize {
+        self.ingest.open_activations()
+    }
+
+    /// Returns the number of events which could not be mapped to a task.
+    pub fn unknown_events(&self) -> usize {
+        self.unknown_events
+    }
+
+    /// Returns the fraction of each of the given number of bins of the trace which is covered
+    /// by bars, per lane.
+    pub fn density(&self, bins: usize) -> Vec<Vec<f32>> {
+        let (min, max) = self.range.unwrap_or((0, 0));
+        let span = (max - min).max(1) as f64 / bins as f64; // ns per bin
+        let level = Summary::level_for(span as u64);
+
+        self.lanes
+            .iter()
+            .map(|lane| {
+                let mut density = vec![0f32; bins];
+                for block in lane.summary.blocks(level, min as u64..max as u64 + 1) {
+                    // Spread the covered time evenly over the block.
+                    let start = (block.start as f64 - min as f64) / span;
+                    let end = (block.end as f64 - min as f64) / span;
+                    let block_density = block.density();
+
+                    let mut bin = start.max(0.0).floor() as usize;
+                    while (bin as f64) < end && bin < bins {
+                        let covered = end.min(bin as f64 + 1.0) - start.max(bin as f64);
+                        density[bin] += covered as f32 * block_density;
+                        bin += 1;
+                    }
+                }
+                density
+            })
+            .collect()
+    }
+
+    pub fn set_retention(&mut self, retention: Retention) -> io::Result<()> {
+        self.spill = if retention.spill {
+            Some(Spill::new()?)
+        } else {
+            None
+        };
+        self.retention = retention;
+        self.enforce_retention();
+        Ok(())
+    }
+
+    /// Returns the timestamp before which all bars were evicted from memory.
+    pub fn evicted_before(&self) -> usize {
+        self.evicted_before
+    }
+
+    /// Returns whether evicted bars are written to the spill file, so they can be read back.
+    pub fn is_spilling(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /// Returns the error which stopped spilling evicted bars to disk, if any.
+    pub fn take_spill_error(&mut self) -> Option<io::Error> {
+        self.spill_error.take()
+    }
+
+    /// Reads the evicted bars which intersect the given range back from the spill file.
+    ///
+    /// Returns no bars if evicted bars are not spilled.
+    pub fn read_spilled(&mut self, range: Range<usize>) -> io::Result<Vec<Bar>> {
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => return Ok(vec![]),
+        };
+        Ok(spill
+            .read(range)?
+            .into_iter()
+            .map(|bar| Bar {
+                start: bar.start,
+                end: bar.end,
+                channel: bar.channel,
+            })
+            .collect())
+    }
+
+    fn enforce_retention(&mut self) {
+        let max = self.range.map_or(0, |(_, max)| max);
+
+        // Evict only once a limit is exceeded by a tenth, as every eviction rebuilds the tree.
+        let mut cutoff = None;
+        if let Some(max_age) = self.retention.max_age_ns() {
+            if max.saturating_sub(self.evicted_before) > max_age + max_age / 10 {
+                cutoff = Some(max - max_age);
+            }
+        }
+        if let Some(max_bars) = self.retention.max_bars(Self::BAR_SIZE) {
+            if self.bar_count > max_bars + max_bars / 10 {
+                let mut ends = self
+                    .bars(0..usize::MAX)
+                    .map(|bar| bar.end)
+                    .collect::<Vec<_>>();
+                let evicted = ends.len().saturating_sub(max_bars);
+                let (_, end, _) = ends.select_nth_unstable(evicted);
+                cutoff = Some(cutoff.unwrap_or(0).max(*end));
+            }
+        }
+
+        if let Some(cutoff) = cutoff {
+            self.evict(cutoff);
+        }
+    }
+
+    /// Removes all bars which ended before the given timestamp from memory.
+    fn evict(&mut self, cutoff: usize) {
+        let mut retained = IntervalTree::new();
+        let mut evicted = vec![];
+        let mut bar_count = 0;
+        for bar in self.bars(0..usize::MAX) {
+            if bar.end < cutoff {
+                evicted.push(SpilledBar {
+                    start: bar.start,
+                    end: bar.end,
+                    channel: bar.channel,
+                });
+            } else {
+                retained.insert(bar.start..bar.end, *bar);
+                bar_count += 1;
+            }
+        }
+        self.bars = retained;
+        self.bar_count = bar_count;
+
+        for lane in &mut self.lanes {
+            lane.summary.evict(cutoff as u64);
+        }
+        if let Some(spill) = &mut self.spill {
+            if let Err(e) = spill.write(&evicted) {
+                // Stop spilling, as the spill file would have a gap from now on.
+                self.spill = None;
+                self.spill_error = Some(e);
+            }
+        }
+        self.evicted_before = self.evicted_before.max(cutoff);
+    }
+}
@@ -0,0 +1,125 @@
+//! Export to the Value Change Dump format of waveform viewers like GTKWave.
+
+use std::io::{self, Write};
+
+use crate::trace::TraceModel;
+
+/// Writes one wire per task, which is high while the task runs, and an integer signal with the
+/// priority of the innermost running task.
+///
+/// The innermost running task is the one which started last. If its priority is unknown, the
+/// signal holds the number of nested running tasks instead. It is zero while no task runs.
+pub fn write(model: &mut TraceModel, mut out: impl Write) -> io::Result<()> {
+    let bars = model.all_bars()?;
+    let lanes = model.lanes();
+    let priority_id = identifier(lanes.len());
+
+    writeln!(out, "$version racer $end")?;
+    writeln!(out, "$timescale 1ns $end")?;
+    writeln!(out, "$scope module rtic $end")?;
+    for (channel, lane) in lanes.iter().enumerate() {
+        writeln!(
+            out,
+            "$var wire 1 {} {} $end",
+            identifier(channel),
+            sanitize(&lane.name)
+        )?;
+    }
+    writeln!(out, "$var integer 32 {} active_priority $end", priority_id)?;
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")?;
+
+    writeln!(out, "#0")?;
+    writeln!(out, "$dumpvars")?;
+    for channel in 0..lanes.len() {
+        writeln!(out, "0{}", identifier(channel))?;
+    }
+    writeln!(out, "b0 {}", priority_id)?;
+    writeln!(out, "$end")?;
+
+    // Every bar starts and ends once. Ends are applied first, so a task which exits and is
+    // entered again at the same time keeps running.
+    let mut changes = Vec::with_capacity(bars.len() * 2);
+    for (index, bar) in bars.iter().enumerate() {
+        changes.push((bar.start, true, index));
+        changes.push((bar.end, false, index));
+    }
+    changes.sort_unstable();
+
+    let mut running = vec![0usize; lanes.len()];
+    let mut is_high = vec![false; lanes.len()];
+    // The indices of all running bars.
+    let mut active: Vec<usize> = vec![];
+    let mut priority = 0u64;
+    let mut touched = vec![];
+
+    let mut changes = changes.into_iter().peekable();
+    while let Some(&(timestamp, _, _)) = changes.peek() {
+        while let Some((_, is_start, index)) = changes.next_if(|change| change.0 == timestamp) {
+            let channel = bars[index].channel;
+            if is_start {
+                running[channel] += 1;
+                active.push(index);
+            } else {
+                running[channel] -= 1;
+                active.retain(|&active| active != index);
+            }
+            touched.push(channel);
+        }
+
+        let mut values = vec![];
+        touched.sort_unstable();
+        touched.dedup();
+        for channel in touched.drain(..) {
+            if is_high[channel] != (running[channel] > 0) {
+                is_high[channel] = running[channel] > 0;
+                values.push(format!("{}{}", is_high[channel] as u8, identifier(channel)));
+            }
+        }
+
+        let innermost = active.iter().max_by_key(|&&index| bars[index].start);
+        let new_priority = match innermost {
+            Some(&index) => lanes[bars[index].channel]
+                .priority
+                .map(u64::from)
+                .unwrap_or(active.len() as u64),
+            None => 0,
+        };
+        if new_priority != priority {
+            priority = new_priority;
+            values.push(format!("b{:b} {}", priority, priority_id));
+        }
+
+        if !values.is_empty() {
+            writeln!(out, "#{}", timestamp)?;
+            for value in values {
+                writeln!(out, "{}", value)?;
+            }
+        }
+    }
+    out.flush()
+}
+
+/// Returns the short identifier code of the signal with the given index, made of the printable
+/// ASCII characters.
+fn identifier(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!') as usize + 1;
+
+    let mut identifier = String::new();
+    loop {
+        identifier.push((FIRST + (index % COUNT) as u8) as char);
+        index /= COUNT;
+        if index == 0 {
+            return identifier;
+        }
+        index -= 1;
+    }
+}
+
+/// Replaces all characters which waveform viewers do not accept in signal names.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
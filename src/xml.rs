@@ -0,0 +1,9 @@
+//! Helpers for writing XML, like the SVG export and the JUnit report of the rules.
+
+/// Escapes the characters which are not allowed in XML text and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::task;
+use rtic_scope_api::{EventType, TaskAction};
+use rtic_scope_frontend_racer::{
+    analyze::{Deadline, Report, Violation},
+    time::TimeFormat,
+    trace::TraceModel,
+};
+
+fn model() -> TraceModel {
+    let mut model = TraceModel::new();
+    model.add_event(1_000, task("app::foo", TaskAction::Entered));
+    model.add_event(41_000, task("app::foo", TaskAction::Exited));
+    model.add_event(50_000, EventType::Overflow);
+    model.add_event(100_000, task("app::foo", TaskAction::Entered));
+    model.add_event(160_000, task("app::foo", TaskAction::Exited));
+    model.add_event(170_000, task("app::bar", TaskAction::Exited));
+    model.add_event(180_000, task("app::bar", TaskAction::Entered));
+    model
+}
+
+#[test]
+fn parse_deadline() {
+    assert_eq!(
+        "app::foo=50us".parse(),
+        Ok(Deadline {
+            task: "app::foo".to_string(),
+            max_ns: 50_000,
+        })
+    );
+    assert!("app::foo".parse::<Deadline>().is_err());
+    assert!("app::foo=fast".parse::<Deadline>().is_err());
+}
+
+#[test]
+fn report() {
+    let deadlines = ["app::foo=50us", "app::missing=1ns"].map(|d| d.parse().unwrap());
+    let report = Report::new(&mut model(), &deadlines, &[]).unwrap();
+
+    assert_eq!(report.tasks.len(), 2);
+    assert_eq!(report.tasks[0].completed, 2);
+    assert_eq!(report.tasks[0].mean_ns, Some(50_000.0));
+    assert_eq!(report.tasks[1].activations, 1);
+    assert_eq!(report.overflows, 1);
+    assert_eq!(report.unmatched_exits, 1);
+    assert_eq!(report.open_activations, 1);
+    assert_eq!(
+        report.violations,
+        [Violation {
+            task: "app::foo".to_string(),
+            start_ns: 100_000,
+            duration_ns: 60_000,
+            deadline_ns: 50_000,
+        }]
+    );
+
+    assert_eq!(report.missing_tasks, ["app::missing"]);
+
+    let mut text = vec![];
+    report
+        .write_text(&mut text, &TimeFormat::default())
+        .unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(
+        text.contains("app::foo at 100us took 60.0us, deadline 50.0us"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("The task app::missing of a deadline does not appear in the trace"),
+        "{}",
+        text
+    );
+}
@@ -0,0 +1,73 @@
+use rtic_scope_frontend_racer::axis::{Axis, Tick};
+
+#[test]
+fn steps_are_round() {
+    // 1 px per ns, so the spacing in px equals the one in ns.
+    assert_eq!(Axis::new(1.0, 1.0).major_ns, 1);
+    assert_eq!(Axis::new(1.0, 1.5).major_ns, 2);
+    assert_eq!(Axis::new(1.0, 3.0).major_ns, 5);
+    assert_eq!(Axis::new(1.0, 6.0).major_ns, 10);
+    assert_eq!(Axis::new(1.0, 120.0).major_ns, 200);
+    assert_eq!(Axis::new(1e-6, 120.0).major_ns, 200_000_000);
+}
+
+#[test]
+fn minor_ticks_subdivide_major_ones() {
+    for zoom in [1.0, 1e-1, 1e-3, 1e-7, 1e-12] {
+        for min_spacing in [1.0, 12.0, 35.0, 120.0] {
+            let axis = Axis::new(zoom, min_spacing);
+            assert_eq!(axis.major_ns % axis.minor_ns, 0, "{:?}", axis);
+            let subdivisions = axis.major_ns / axis.minor_ns;
+            assert!(
+                [4, 5].contains(&subdivisions) || axis.major_ns < 10,
+                "{:?}",
+                axis
+            );
+        }
+    }
+}
+
+#[test]
+fn ticks_are_aligned() {
+    let axis = Axis::new(1e-3, 120.0);
+    assert_eq!(
+        axis,
+        Axis {
+            major_ns: 200_000,
+            minor_ns: 50_000
+        }
+    );
+
+    let ticks = axis.ticks(130_000.0..420_000.0).collect::<Vec<_>>();
+    let tick = |ns, major| Tick { ns, major };
+    assert_eq!(
+        ticks,
+        [
+            tick(150_000, false),
+            tick(200_000, true),
+            tick(250_000, false),
+            tick(300_000, false),
+            tick(350_000, false),
+            tick(400_000, true),
+        ]
+    );
+}
+
+#[test]
+fn ticks_far_into_a_trace() {
+    // A 1 us wide view two hours into a trace.
+    let start = 7_200_000_000_000.0;
+    let axis = Axis::new(1.0, 120.0);
+    let ticks = axis.ticks(start..start + 1000.0).collect::<Vec<_>>();
+    assert_eq!(ticks.first().unwrap().ns, 7_200_000_000_000);
+    assert_eq!(ticks.last().unwrap().ns, 7_200_000_001_000);
+    assert!(ticks.iter().all(|tick| tick.ns % axis.minor_ns == 0));
+}
+
+#[test]
+fn huge_ranges() {
+    let axis = Axis::new(1e-30, 120.0);
+    assert_eq!(axis.major_ns, 5_000_000_000_000_000_000);
+    // Minor ticks every 1e18 ns up to the largest u64.
+    assert_eq!(axis.ticks(0.0..f64::MAX).count(), 19);
+}
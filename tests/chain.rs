@@ -0,0 +1,74 @@
+mod common;
+
+use common::model;
+use rtic_scope_frontend_racer::chain::{Chain, Latency};
+
+#[test]
+fn parse() {
+    let chain = "UART0 -> parse → send_reply".parse::<Chain>().unwrap();
+    assert_eq!(chain.tasks, ["UART0", "parse", "send_reply"]);
+    assert_eq!(chain.to_string(), "UART0 -> parse -> send_reply");
+
+    assert!("UART0".parse::<Chain>().is_err());
+    assert!("UART0 -> -> parse".parse::<Chain>().is_err());
+}
+
+#[test]
+fn instances() {
+    let mut model = model(&[
+        ("UART0", 0, 10),
+        // Preempted by the interrupt, but caused by it.
+        ("parse", 5, 30),
+        ("send_reply", 40, 50),
+        // The parse task is never spawned by this interrupt.
+        ("UART0", 100, 110),
+        ("UART0", 200, 210),
+        ("parse", 220, 230),
+        ("send_reply", 240, 260),
+        // The chain is broken by a later parse, which was caused by the next interrupt.
+        ("UART0", 300, 310),
+        ("parse", 320, 330),
+        ("parse", 420, 430),
+    ]);
+    let chain = "UART0 -> parse -> send_reply".parse::<Chain>().unwrap();
+    let instances = chain.instances(&mut model).unwrap();
+
+    let starts = instances
+        .iter()
+        .map(|instance| {
+            instance
+                .bars
+                .iter()
+                .map(|bar| bar.start)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(starts, [[0, 5, 40], [200, 220, 240]]);
+    assert_eq!(instances[0].latency_ns(), 50);
+    assert_eq!(instances[1].latency_ns(), 60);
+
+    let missing = "UART0 -> missing".parse::<Chain>().unwrap();
+    assert!(missing.instances(&mut model).unwrap().is_empty());
+}
+
+#[test]
+fn latency() {
+    let activations = (0..100)
+        .flat_map(|i| {
+            let start = i * 1000;
+            [("a", start, start + 1), ("b", start + 2, start + 3 + i)]
+        })
+        .collect::<Vec<_>>();
+    let chain = "a -> b".parse::<Chain>().unwrap();
+    let latency = Latency::new(&chain.instances(&mut model(&activations)).unwrap());
+
+    assert_eq!(latency.instances, 100);
+    assert_eq!(latency.min_ns, Some(3));
+    assert_eq!(latency.mean_ns, Some(52.5));
+    assert_eq!(latency.median_ns, Some(52));
+    assert_eq!(latency.p90_ns, Some(92));
+    assert_eq!(latency.p99_ns, Some(101));
+    assert_eq!(latency.max_ns, Some(102));
+
+    assert_eq!(Latency::new(&[]).median_ns, None);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::task;
+use rtic_scope_api::{EventType, TaskAction};
+use rtic_scope_frontend_racer::{chrome, trace::TraceModel};
+use serde_json::Value;
+
+#[test]
+fn complete_events() {
+    let mut model = TraceModel::new();
+    model.add_event(1000, task("app::foo", TaskAction::Entered));
+    model.add_event(1500, EventType::Overflow);
+    model.add_event(3250, task("app::foo", TaskAction::Exited));
+
+    let mut out = vec![];
+    chrome::write(&mut model, &mut out).unwrap();
+
+    let trace: Value = serde_json::from_slice(&out).unwrap();
+    let events = trace["traceEvents"].as_array().unwrap();
+    let find = |ph: &str| events.iter().find(|event| event["ph"] == ph).unwrap();
+
+    let thread = events
+        .iter()
+        .find(|event| event["name"] == "thread_name")
+        .unwrap();
+    assert_eq!(thread["args"]["name"], "app::foo");
+
+    let task = find("X");
+    assert_eq!(task["name"], "app::foo");
+    assert_eq!(task["ts"], 1.0);
+    assert_eq!(task["dur"], 2.25);
+    assert_eq!(task["tid"], thread["tid"]);
+
+    assert_eq!(find("i")["ts"], 1.5);
+}
+
+#[test]
+fn invalid_trace_lines() {
+    let error = TraceModel::read("\n{}\n".as_bytes()).err().unwrap();
+    assert!(error.to_string().contains("line 2"), "{}", error);
+}
@@ -0,0 +1,34 @@
+//! Builders of the traces which the integration tests are run on.
+
+// Each test crate compiles this module on its own and uses only some of it.
+#![allow(dead_code)]
+
+use rtic_scope_api::{EventType, TaskAction};
+use rtic_scope_frontend_racer::trace::TraceModel;
+
+pub fn task(name: &str, action: TaskAction) -> EventType {
+    EventType::Task {
+        name: name.to_string(),
+        action,
+    }
+}
+
+/// Builds a model with the given activations of (task, start, end) in ns.
+///
+/// The events are added in the order of their timestamps, where exits come before entries of
+/// the same timestamp, so back-to-back activations are not nested. Activations which start at
+/// the same time are entered in the given order.
+pub fn model(activations: &[(&str, usize, usize)]) -> TraceModel {
+    let mut events = vec![];
+    for &(name, start, end) in activations {
+        events.push((start, 1, task(name, TaskAction::Entered)));
+        events.push((end, 0, task(name, TaskAction::Exited)));
+    }
+    events.sort_by_key(|&(timestamp, order, _)| (timestamp, order));
+
+    let mut model = TraceModel::new();
+    for (timestamp, _, event) in events {
+        model.add_event(timestamp, event);
+    }
+    model
+}
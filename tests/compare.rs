@@ -0,0 +1,56 @@
+mod common;
+
+use common::model;
+use rtic_scope_frontend_racer::{
+    compare::{self, Alignment},
+    trace::TraceModel,
+};
+
+#[test]
+fn alignment_offsets() {
+    let mut base = model(&[("a", 100, 200), ("b", 300, 350), ("b", 500, 550)]);
+    let mut other = model(&[("a", 1000, 1100), ("b", 1250, 1300), ("b", 1400, 1480)]);
+
+    let offset = |alignment: Alignment, base: &mut TraceModel, other: &mut TraceModel| {
+        alignment.offset(base, other).unwrap()
+    };
+    assert_eq!(offset(Alignment::Start, &mut base, &mut other), Some(-900));
+    let second_b = Alignment::Activation {
+        task: "b".to_string(),
+        index: 1,
+    };
+    assert_eq!(offset(second_b, &mut base, &mut other), Some(-900));
+    let third_b = Alignment::Activation {
+        task: "b".to_string(),
+        index: 2,
+    };
+    assert_eq!(offset(third_b, &mut base, &mut other), None);
+    let missing = Alignment::Activation {
+        task: "c".to_string(),
+        index: 0,
+    };
+    assert_eq!(offset(missing, &mut base, &mut other), None);
+    assert_eq!(
+        offset(Alignment::Manual(42), &mut base, &mut other),
+        Some(42)
+    );
+}
+
+#[test]
+fn stats_delta() {
+    let base = model(&[("a", 100, 200), ("b", 300, 350)]);
+    let other = model(&[("c", 0, 10), ("a", 100, 250), ("a", 300, 400)]);
+
+    let deltas = compare::stats_delta(&base, &other);
+    let names = deltas.iter().map(|d| d.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+
+    assert_eq!(deltas[0].completed(), 1);
+    assert_eq!(deltas[0].mean_ns(), Some(25.0));
+    assert_eq!(deltas[0].max_ns(), Some(50));
+
+    assert_eq!(deltas[1].completed(), -1);
+    assert_eq!(deltas[1].mean_ns(), None);
+    assert_eq!(deltas[2].base.completed, 0);
+    assert_eq!(deltas[2].other.completed, 1);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::task;
+use rtic_scope_api::TaskAction;
+use rtic_scope_frontend_racer::{csv, trace::TraceModel};
+
+fn model() -> TraceModel {
+    let mut model = TraceModel::new();
+    model.add_event(100, task("app::foo", TaskAction::Entered));
+    model.add_event(150, task("app::foo", TaskAction::Exited));
+    model.add_event(200, task("app::bar, baz", TaskAction::Entered));
+    model.add_event(300, task("app::foo", TaskAction::Entered));
+    model.add_event(380, task("app::foo", TaskAction::Exited));
+    model.set_priority(0, Some(2));
+    model
+}
+
+#[test]
+fn bars() {
+    let mut out = vec![];
+    csv::write_bars(&mut model(), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "task,start_ns,end_ns,duration_ns,channel\n\
+         app::foo,100,150,50,0\n\
+         app::foo,300,380,80,0\n"
+    );
+}
+
+#[test]
+fn stats() {
+    let mut out = vec![];
+    csv::write_stats(&mut model(), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "task,channel,priority,activations,completed,total_ns,min_ns,max_ns,mean_ns,mean_period_ns\n\
+         app::foo,0,2,2,2,130,50,80,65,200\n\
+         \"app::bar, baz\",1,,1,0,0,,,,\n"
+    );
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::task;
+use rtic_scope_api::TaskAction;
+use rtic_scope_frontend_racer::{
+    layout::{self, LaneOrder, Layout, Row},
+    trace::TraceModel,
+};
+
+/// Builds a model with one activation of each task with the given priority.
+fn model(tasks: &[(&str, Option<u8>)]) -> TraceModel {
+    let mut model = TraceModel::new();
+    for (i, &(name, priority)) in tasks.iter().enumerate() {
+        model.add_event(i, task(name, TaskAction::Entered));
+        model.set_priority(i, priority);
+    }
+    model
+}
+
+fn row(priority: Option<u8>, channels: &[usize]) -> Row {
+    Row {
+        priority,
+        channels: channels.to_vec(),
+    }
+}
+
+#[test]
+fn orders() {
+    let model = model(&[
+        ("idle", None),
+        ("uart", Some(3)),
+        ("parse", Some(1)),
+        ("timer", Some(3)),
+        ("log", None),
+    ]);
+
+    let layout = Layout::new(model.lanes(), LaneOrder::Appearance);
+    assert_eq!(layout.rows().len(), 5);
+    assert_eq!(layout.row(3), 3);
+    assert!(!(0..5).any(|row| layout.starts_group(row)));
+
+    let layout = Layout::new(model.lanes(), LaneOrder::Priority);
+    assert_eq!(
+        layout.rows(),
+        [
+            row(Some(3), &[1]),
+            row(Some(3), &[3]),
+            row(Some(1), &[2]),
+            row(None, &[0]),
+            row(None, &[4]),
+        ]
+    );
+    assert_eq!(layout.row(0), 3);
+    assert_eq!(
+        (0..5)
+            .map(|row| layout.starts_group(row))
+            .collect::<Vec<_>>(),
+        [false, false, true, true, false]
+    );
+
+    let layout = Layout::new(model.lanes(), LaneOrder::PriorityLevel);
+    assert_eq!(
+        layout.rows(),
+        [
+            row(Some(3), &[1, 3]),
+            row(Some(1), &[2]),
+            row(None, &[0]),
+            row(None, &[4]),
+        ]
+    );
+    assert_eq!(layout.row(3), 0);
+    assert_eq!(layout.row(4), 3);
+}
+
+#[test]
+fn app_priorities() {
+    let priorities = layout::app_priorities(
+        r#"
+        #[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [EXTI0])]
+        mod app {
+            #[task(binds = UART0, priority = 3, shared = [buffer, queue], local = [a: u8 = 0])]
+            fn uart0(cx: uart0::Context) {}
+
+            #[task(shared = [buffer])]
+            #[inline(never)]
+            fn parse(cx: parse::Context) {}
+
+            #[task]
+            fn send_reply(_: send_reply::Context) {}
+
+            #[task(priority = 2)]
+            async fn blink(_: blink::Context) {}
+        }
+        "#,
+    );
+
+    let priority = |task: &str| layout::priority(&priorities, task);
+    assert_eq!(priority("uart0"), Some(3));
+    assert_eq!(priority("UART0"), Some(3));
+    assert_eq!(priority("app::parse"), Some(1));
+    assert_eq!(priority("send_reply"), Some(1));
+    assert_eq!(priority("blink"), Some(2));
+    assert_eq!(priority("idle"), None);
+}
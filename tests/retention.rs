@@ -0,0 +1,115 @@
+mod common;
+
+use common::task;
+use rtic_scope_api::TaskAction;
+use rtic_scope_frontend_racer::{
+    retention::Retention,
+    trace::{Bar, TraceModel},
+};
+
+/// Builds a model with the retention, and adds activations of 50ns every 100ns which alternate
+/// between two tasks.
+fn model(retention: Retention, activations: usize) -> TraceModel {
+    let mut model = TraceModel::new();
+    model.set_retention(retention).unwrap();
+    for i in 0..activations {
+        let name = if i % 2 == 0 { "a" } else { "b" };
+        model.add_event(i * 100, task(name, TaskAction::Entered));
+        model.add_event(i * 100 + 50, task(name, TaskAction::Exited));
+    }
+    model
+}
+
+fn bar(start: usize, channel: usize) -> Bar {
+    Bar {
+        start,
+        end: start + 50,
+        channel,
+    }
+}
+
+#[test]
+fn max_bars() {
+    let retention = |bars, memory_mib| Retention {
+        bars,
+        memory_mib,
+        ..Retention::default()
+    };
+    assert_eq!(retention(None, None).max_bars(64), None);
+    assert_eq!(retention(Some(100), None).max_bars(64), Some(100));
+    assert_eq!(retention(None, Some(1)).max_bars(1024), Some(1024));
+    assert_eq!(retention(Some(100), Some(1)).max_bars(1024), Some(100));
+    assert_eq!(retention(Some(2000), Some(1)).max_bars(1024), Some(1024));
+    // At least one bar is kept.
+    assert_eq!(retention(Some(0), None).max_bars(64), Some(1));
+    assert_eq!(retention(None, Some(1)).max_bars(usize::MAX), Some(1));
+}
+
+#[test]
+fn evicts_oldest_bars() {
+    let retention = Retention {
+        bars: Some(10),
+        ..Retention::default()
+    };
+
+    // Up to a tenth more bars than the limit are kept.
+    let model = model(retention.clone(), 11);
+    assert_eq!(model.bar_count(), 11);
+    assert_eq!(model.evicted_before(), 0);
+
+    let mut model = self::model(retention, 12);
+    assert_eq!(model.bar_count(), 10);
+    assert_eq!(model.evicted_before(), 250);
+    let bars = model.sorted_bars();
+    assert_eq!(bars.first(), Some(&bar(200, 0)));
+    assert_eq!(bars.last(), Some(&bar(1100, 1)));
+
+    // The statistics still include the evicted bars, but they are not spilled.
+    assert_eq!(model.stats()[0].completed, 6);
+    assert_eq!(model.stats()[0].first_start, Some(0));
+    assert!(model.read_spilled(0..usize::MAX).unwrap().is_empty());
+    assert_eq!(model.all_bars().unwrap().len(), 10);
+}
+
+#[test]
+fn evicts_old_bars() {
+    let retention = Retention {
+        seconds: Some(1e-6),
+        ..Retention::default()
+    };
+
+    // The latest event is at 1050ns, which is within a tenth of the limit.
+    let model = model(retention.clone(), 11);
+    assert_eq!(model.evicted_before(), 0);
+    assert_eq!(model.bar_count(), 11);
+
+    let model = self::model(retention, 12);
+    assert_eq!(model.evicted_before(), 150);
+    assert_eq!(model.sorted_bars().first(), Some(&bar(100, 1)));
+    assert_eq!(model.bar_count(), 11);
+}
+
+#[test]
+fn spill_round_trip() {
+    let retention = Retention {
+        bars: Some(10),
+        spill: true,
+        ..Retention::default()
+    };
+    let mut spilled = model(retention, 100);
+    let mut unlimited = model(Retention::default(), 100);
+
+    assert!(spilled.bar_count() <= 11);
+    assert!(spilled.evicted_before() > 0);
+    assert!(spilled.take_spill_error().is_none());
+    assert_eq!(spilled.all_bars().unwrap(), unlimited.all_bars().unwrap());
+
+    // Only the chunks and bars which intersect the range are read back.
+    let mut bars = spilled.read_spilled(1000..1250).unwrap();
+    bars.sort_by_key(|bar| bar.start);
+    assert_eq!(bars, [bar(1000, 0), bar(1100, 1), bar(1200, 0)]);
+    assert!(spilled
+        .read_spilled(spilled.evicted_before()..usize::MAX)
+        .unwrap()
+        .is_empty());
+}
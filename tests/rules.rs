@@ -0,0 +1,151 @@
+mod common;
+
+use common::model;
+use rtic_scope_frontend_racer::{
+    rules::{self, Assertion, Comparison, Statistic},
+    time::TimeFormat,
+    trace::TraceModel,
+};
+
+/// Returns whether each rule passed.
+fn check(rules: &str, model: &mut TraceModel) -> Vec<bool> {
+    let rules = rules::parse(rules).unwrap();
+    rules::check(&rules, model, &TimeFormat::default())
+        .unwrap()
+        .iter()
+        .map(|outcome| outcome.is_pass())
+        .collect()
+}
+
+#[test]
+fn parse() {
+    let rules = rules::parse(
+        "# Timing of the UART handler\n\
+         \n\
+         task uart max duration < 50us\n\
+         task tick period 1ms ±5%\n\
+         task reply always follows parse within 10 us\n\
+         no overflows\n",
+    )
+    .unwrap();
+
+    let assertions = rules.iter().map(|rule| &rule.assertion).collect::<Vec<_>>();
+    assert_eq!(
+        assertions,
+        [
+            &Assertion::Duration {
+                task: "uart".to_string(),
+                statistic: Statistic::Max,
+                comparison: Comparison::Less,
+                limit_ns: 50_000,
+            },
+            &Assertion::Period {
+                task: "tick".to_string(),
+                period_ns: 1_000_000,
+                tolerance_percent: 5.0,
+            },
+            &Assertion::Follows {
+                task: "reply".to_string(),
+                cause: "parse".to_string(),
+                within_ns: 10_000,
+            },
+            &Assertion::NoOverflows,
+        ]
+    );
+    assert_eq!(rules[0].line, 3);
+
+    let error = rules::parse("no overflows\ntask uart fast\n").unwrap_err();
+    assert_eq!(error.line, 2);
+
+    for tolerance in ["+-NaN%", "+-inf%", "+--5%", "5%"] {
+        let error = rules::parse(&format!(
+            "no overflows\ntask tick period 1ms {}\n",
+            tolerance
+        ))
+        .unwrap_err();
+        assert_eq!(error.line, 2, "{}", tolerance);
+        assert!(error.message.contains("invalid tolerance"), "{}", error);
+    }
+}
+
+#[test]
+fn durations() {
+    let mut model = model(&[("uart", 0, 40_000), ("uart", 100_000, 160_000)]);
+    assert_eq!(
+        check(
+            "task uart max_duration < 50us\n\
+             task uart mean_duration <= 50us\n\
+             task uart min duration >= 40us\n\
+             task missing max_duration < 1s\n",
+            &mut model
+        ),
+        [false, true, true, false]
+    );
+}
+
+#[test]
+fn periods() {
+    let mut model = model(&[
+        ("tick", 0, 1_000),
+        ("tick", 1_000_000, 1_001_000),
+        ("tick", 2_040_000, 2_041_000),
+    ]);
+    assert_eq!(
+        check(
+            "task tick period 1ms +-5%\ntask tick period 1ms +-1%\n",
+            &mut model
+        ),
+        [true, false]
+    );
+}
+
+#[test]
+fn follows() {
+    let mut model = model(&[
+        ("parse", 0, 10_000),
+        ("reply", 15_000, 20_000),
+        ("parse", 100_000, 110_000),
+        ("reply", 130_000, 140_000),
+    ]);
+    assert_eq!(
+        check(
+            "task reply follows parse within 20us\n\
+             task reply follows parse within 10us\n",
+            &mut model
+        ),
+        [true, false]
+    );
+
+    // An activation which preempts the cause follows it as well.
+    let mut preempted = self::model(&[("parse", 0, 30_000), ("reply", 5_000, 10_000)]);
+    assert_eq!(
+        check("task reply follows parse within 1us\n", &mut preempted),
+        [true]
+    );
+}
+
+#[test]
+fn junit() {
+    let mut model = model(&[("uart", 0, 60_000)]);
+    let rules = rules::parse("task uart max_duration < 50us\nno overflows\n").unwrap();
+    let outcomes = rules::check(&rules, &mut model, &TimeFormat::default()).unwrap();
+
+    let mut xml = vec![];
+    rules::write_junit(&outcomes, &mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(
+        xml.contains(r#"<testsuites tests="2" failures="1">"#),
+        "{}",
+        xml
+    );
+    assert!(
+        xml.contains(r#"<testcase name="line 1: task uart max_duration &lt; 50us""#),
+        "{}",
+        xml
+    );
+    assert!(
+        xml.contains(r#"<failure message="The max duration is 60.0us">"#),
+        "{}",
+        xml
+    );
+}
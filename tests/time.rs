@@ -0,0 +1,166 @@
+use proptest::prelude::*;
+use rtic_scope_frontend_racer::time::{self, LongUnits, TimeFormat};
+
+const SI: TimeFormat = TimeFormat {
+    significant_digits: 3,
+    long_units: LongUnits::Si,
+};
+const CLOCK: TimeFormat = TimeFormat {
+    significant_digits: 3,
+    long_units: LongUnits::Clock,
+};
+
+/// Parses a decimal number of the given unit into ns, exactly.
+fn parse_number(number: &str, unit_ns: u128) -> u128 {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let mut ns = integer.parse::<u128>().unwrap() * unit_ns;
+    let mut digit_ns = unit_ns;
+    for digit in fraction.chars() {
+        assert_eq!(digit_ns % 10, 0, "too many decimals in {:?}", number);
+        digit_ns /= 10;
+        ns += digit.to_digit(10).unwrap() as u128 * digit_ns;
+    }
+    ns
+}
+
+/// Parses a formatted time back into ns.
+fn parse(formatted: &str) -> u128 {
+    formatted
+        .split(' ')
+        .map(|part| {
+            let split = part.find(|c: char| c.is_alphabetic()).unwrap();
+            let (number, unit) = part.split_at(split);
+            let unit_ns = match unit {
+                "ns" => 1,
+                "us" => 1_000,
+                "ms" => 1_000_000,
+                "s" => 1_000_000_000,
+                "ks" => 1_000_000_000_000,
+                "Ms" => 1_000_000_000_000_000,
+                "min" => 60_000_000_000,
+                "h" => 3_600_000_000_000,
+                _ => panic!("unknown unit in {:?}", formatted),
+            };
+            parse_number(number, unit_ns)
+        })
+        .sum()
+}
+
+#[test]
+fn examples() {
+    assert_eq!(SI.format(0), "0ns");
+    assert_eq!(SI.format(999), "999ns");
+    assert_eq!(SI.format(1_234), "1.23us");
+    assert_eq!(SI.format(999_600), "1.00ms");
+    assert_eq!(SI.format(12_345_678_901), "12.3s");
+    assert_eq!(SI.format(1_500_000_000_000), "1.50ks");
+    assert_eq!(SI.format(u64::MAX), "18400Ms");
+    assert_eq!(CLOCK.format(59_990_000_000), "1min 00.0s");
+    assert_eq!(CLOCK.format(3_723_456_000_000), "1h 02min 03s");
+
+    assert_eq!(SI.format_with_resolution(1_500_000_100, 100), "1.5000001s");
+    assert_eq!(SI.format_with_resolution(2_000, 1_000), "2us");
+    assert_eq!(SI.format_with_resolution(2_500, 500), "2.5us");
+    assert_eq!(SI.format_with_resolution(1_234, 1_000_000), "0ns");
+    assert_eq!(
+        CLOCK.format_with_resolution(3_723_456_000_000, 100_000_000),
+        "1h 02min 03.5s"
+    );
+    assert_eq!(
+        CLOCK.format_with_resolution(3_723_456_000_000, 60_000_000_000),
+        "1h 02min"
+    );
+    assert_eq!(
+        CLOCK.format_with_resolution(1_000_000_000_000, 3_600_000_000_000),
+        "0h"
+    );
+}
+
+fn format_strategy() -> impl Strategy<Value = TimeFormat> {
+    (1u32..12, prop::bool::ANY).prop_map(|(significant_digits, clock)| TimeFormat {
+        significant_digits,
+        long_units: if clock {
+            LongUnits::Clock
+        } else {
+            LongUnits::Si
+        },
+    })
+}
+
+/// Timestamps spread over all magnitudes.
+fn ns_strategy() -> impl Strategy<Value = u64> {
+    (0u32..64, any::<u64>()).prop_map(|(bits, ns)| ns >> bits)
+}
+
+#[test]
+fn parse_durations() {
+    assert_eq!(time::parse("50us"), Some(50_000));
+    assert_eq!(time::parse("50 µs"), Some(50_000));
+    assert_eq!(time::parse("1.5ms"), Some(1_500_000));
+    assert_eq!(time::parse(".25s"), Some(250_000_000));
+    assert_eq!(time::parse("2min"), Some(120_000_000_000));
+    assert_eq!(time::parse("0.4ns"), Some(0));
+    assert_eq!(time::parse("12"), None);
+    assert_eq!(time::parse("us"), None);
+    assert_eq!(time::parse("1.2.3ms"), None);
+    assert_eq!(time::parse("1.2.3ns"), None);
+    assert_eq!(
+        time::parse("0.99999999999999999999999999999Ms"),
+        Some(1_000_000_000_000_000)
+    );
+    assert_eq!(time::parse("0.49999999999999999999999999999ns"), Some(0));
+    assert_eq!(time::parse("99999999999h"), None);
+}
+
+proptest! {
+    #[test]
+    fn format_is_within_significant_digits(format in format_strategy(), ns in ns_strategy()) {
+        let formatted = format.format(ns);
+        let error = (parse(&formatted) as i128 - ns as i128).unsigned_abs();
+        let digits = ns.to_string().len() as u32;
+        let allowed = 10u128.pow(digits.saturating_sub(format.significant_digits)) / 2;
+        // The clock format always shows at least whole seconds.
+        let allowed = if format.long_units == LongUnits::Clock && ns >= 60_000_000_000 {
+            allowed.max(500_000_000)
+        } else {
+            allowed
+        };
+        prop_assert!(error <= allowed, "{} formatted as {}", ns, formatted);
+    }
+
+    #[test]
+    fn format_is_monotonic(format in format_strategy(), a in ns_strategy(), b in ns_strategy()) {
+        let (a, b) = (a.min(b), a.max(b));
+        prop_assert!(parse(&format.format(a)) <= parse(&format.format(b)));
+    }
+
+    #[test]
+    fn grid_labels_are_exact(
+        format in format_strategy(),
+        mantissa in prop::sample::select(vec![1u64, 2, 5]),
+        exponent in 0u32..13,
+        index in 0u64..100_000,
+    ) {
+        // Grid lines are placed at multiples of 1, 2 or 5 times a power of ten.
+        let spacing = mantissa * 10u64.pow(exponent);
+        let ns = index * spacing;
+        let formatted = format.format_with_resolution(ns, spacing);
+        let next = format.format_with_resolution(ns + spacing, spacing);
+        if format.long_units == LongUnits::Si || spacing < 1_000_000_000 {
+            prop_assert_eq!(parse(&formatted), ns as u128, "{}", formatted);
+        }
+        if format.long_units == LongUnits::Si {
+            prop_assert_eq!(time::parse(&formatted), Some(ns), "{}", formatted);
+        }
+        prop_assert_ne!(formatted, next);
+    }
+
+    #[test]
+    fn format_with_resolution_never_panics(
+        format in format_strategy(),
+        ns in any::<u64>(),
+        resolution in any::<u64>(),
+    ) {
+        format.format_with_resolution(ns, resolution);
+    }
+}
@@ -0,0 +1,254 @@
+//! Golden-trace tests which feed canned event sequences through [`TraceModel`] and check the
+//! resulting bars and statistics.
+
+mod common;
+
+use common::task;
+use rtic_scope_api::{EventType, TaskAction};
+use rtic_scope_frontend_racer::trace::{Bar, TaskStats, TraceModel};
+
+fn enter(name: &str) -> EventType {
+    task(name, TaskAction::Entered)
+}
+
+fn exit(name: &str) -> EventType {
+    task(name, TaskAction::Exited)
+}
+
+/// Builds a model from chunks of events which share a timestamp, like an `EventChunk`.
+fn model(chunks: Vec<(usize, Vec<EventType>)>) -> TraceModel {
+    let mut model = TraceModel::new();
+    for (timestamp, events) in chunks {
+        for event in events {
+            model.add_event(timestamp, event);
+        }
+    }
+    model
+}
+
+fn bar(start: usize, end: usize, channel: usize) -> Bar {
+    Bar {
+        start,
+        end,
+        channel,
+    }
+}
+
+fn names(model: &TraceModel) -> Vec<&str> {
+    model
+        .lanes()
+        .iter()
+        .map(|lane| lane.name.as_str())
+        .collect()
+}
+
+#[test]
+fn sequential_tasks() {
+    let model = model(vec![
+        (100, vec![enter("a")]),
+        (200, vec![exit("a"), enter("b")]),
+        (250, vec![exit("b")]),
+        (300, vec![enter("a")]),
+        (450, vec![exit("a")]),
+    ]);
+
+    assert_eq!(names(&model), ["a", "b"]);
+    assert_eq!(
+        model.sorted_bars(),
+        [bar(100, 200, 0), bar(200, 250, 1), bar(300, 450, 0)]
+    );
+    assert_eq!(model.range(), Some((100, 450)));
+    assert_eq!(model.lanes()[0].activations, 2);
+    assert_eq!(model.lanes()[1].activations, 1);
+    assert_eq!(
+        model.stats()[0],
+        TaskStats {
+            completed: 2,
+            total_ns: 250,
+            min_ns: Some(100),
+            max_ns: Some(150),
+            first_start: Some(100),
+            last_start: Some(300),
+        }
+    );
+    assert_eq!(model.stats()[0].mean_ns(), Some(125.0));
+    assert_eq!(model.stats()[0].mean_period_ns(), Some(200.0));
+    assert_eq!(model.stats()[1].mean_period_ns(), None);
+    assert_eq!(model.open_activations(), 0);
+    assert_eq!(model.unmatched_exits(), 0);
+}
+
+#[test]
+fn nested_preemption() {
+    // A low priority task is preempted by a higher one, which is preempted in turn.
+    let model = model(vec![
+        (0, vec![enter("low")]),
+        (10, vec![enter("mid")]),
+        (20, vec![enter("high")]),
+        (25, vec![exit("high")]),
+        (40, vec![exit("mid")]),
+        (100, vec![exit("low")]),
+    ]);
+
+    assert_eq!(names(&model), ["low", "mid", "high"]);
+    assert_eq!(
+        model.sorted_bars(),
+        [bar(0, 100, 0), bar(10, 40, 1), bar(20, 25, 2)]
+    );
+    // The preempted task spans the whole activation, including the time it was preempted.
+    assert_eq!(model.stats()[0].total_ns, 100);
+    assert_eq!(
+        model
+            .bars(22..23)
+            .map(|bar| bar.channel)
+            .collect::<std::collections::BTreeSet<_>>(),
+        [0, 1, 2].into_iter().collect()
+    );
+    assert_eq!(
+        model
+            .bars(50..60)
+            .map(|bar| bar.channel)
+            .collect::<Vec<_>>(),
+        [0]
+    );
+}
+
+#[test]
+fn reentrant_task() {
+    // A task which preempts itself closes its innermost activation first.
+    let model = model(vec![
+        (0, vec![enter("a")]),
+        (10, vec![enter("a")]),
+        (20, vec![exit("a")]),
+        (30, vec![exit("a")]),
+    ]);
+
+    assert_eq!(model.sorted_bars(), [bar(0, 30, 0), bar(10, 20, 0)]);
+    assert_eq!(model.lanes()[0].activations, 2);
+    assert_eq!(model.stats()[0].min_ns, Some(10));
+    assert_eq!(model.stats()[0].max_ns, Some(30));
+}
+
+#[test]
+fn overflow() {
+    // Events are lost after an overflow, here the exit of `a`.
+    let model = model(vec![
+        (0, vec![enter("a")]),
+        (10, vec![EventType::Overflow]),
+        (20, vec![enter("b")]),
+        (30, vec![exit("b")]),
+    ]);
+
+    assert_eq!(model.overflows(), 1);
+    assert_eq!(model.sorted_bars(), [bar(20, 30, 1)]);
+    assert_eq!(model.open_activations(), 1);
+    assert_eq!(model.stats()[0].completed, 0);
+    assert_eq!(model.stats()[0].mean_ns(), None);
+    assert_eq!(model.range(), Some((0, 30)));
+}
+
+#[test]
+fn unmatched_exits() {
+    // The enter of `a` was lost, and `b` exits twice.
+    let model = model(vec![
+        (0, vec![exit("a")]),
+        (10, vec![enter("b")]),
+        (20, vec![exit("b")]),
+        (30, vec![exit("b")]),
+    ]);
+
+    assert_eq!(model.unmatched_exits(), 2);
+    assert_eq!(names(&model), ["b"]);
+    assert_eq!(model.channel("a"), None);
+    assert_eq!(model.channel("b"), Some(0));
+    assert_eq!(model.sorted_bars(), [bar(10, 20, 0)]);
+    assert_eq!(model.open_activations(), 0);
+}
+
+#[test]
+fn unknown_events() {
+    let model = model(vec![
+        (0, vec![EventType::Unknown(vec![0x01])]),
+        (10, vec![EventType::Invalid(vec![0xff])]),
+    ]);
+
+    assert_eq!(model.unknown_events(), 2);
+    assert!(model.lanes().is_empty());
+    assert_eq!(model.bar_count(), 0);
+}
+
+#[test]
+fn out_of_order_chunks() {
+    // The chunk of `b` arrives after a later one of `a`.
+    let model = model(vec![
+        (100, vec![enter("a")]),
+        (200, vec![exit("a")]),
+        (150, vec![enter("b")]),
+        (180, vec![exit("b")]),
+        (50, vec![enter("c")]),
+        (60, vec![exit("c")]),
+    ]);
+
+    assert_eq!(
+        model.sorted_bars(),
+        [bar(50, 60, 2), bar(100, 200, 0), bar(150, 180, 1)]
+    );
+    assert_eq!(model.range(), Some((50, 200)));
+}
+
+#[test]
+fn late_exit() {
+    // The exit of the first activation arrives after the task was entered again, so it closes
+    // the activation which started before it instead of the innermost one.
+    let model = model(vec![
+        (100, vec![enter("a")]),
+        (300, vec![enter("a")]),
+        (200, vec![exit("a")]),
+        (400, vec![exit("a")]),
+    ]);
+
+    assert_eq!(model.sorted_bars(), [bar(100, 200, 0), bar(300, 400, 0)]);
+    assert_eq!(model.unmatched_exits(), 0);
+}
+
+#[test]
+fn exit_before_enter() {
+    // An exit which is older than every open activation of the task cannot be matched.
+    let model = model(vec![(100, vec![enter("a")]), (50, vec![exit("a")])]);
+
+    assert_eq!(model.unmatched_exits(), 1);
+    assert_eq!(model.open_activations(), 1);
+    assert_eq!(model.bar_count(), 0);
+}
+
+#[test]
+fn clear() {
+    let mut model = model(vec![(0, vec![enter("a")]), (10, vec![exit("a")])]);
+    model.clear();
+
+    assert!(model.lanes().is_empty());
+    assert_eq!(model.bar_count(), 0);
+    assert_eq!(model.range(), None);
+}
+
+#[test]
+fn events_rebuild_trace() {
+    let mut original = model(vec![
+        (100, vec![enter("a")]),
+        (150, vec![enter("b"), EventType::Overflow]),
+        (200, vec![exit("b"), exit("a"), enter("a")]),
+        (300, vec![exit("a"), enter("b")]),
+    ]);
+
+    let mut rebuilt = TraceModel::new();
+    for (timestamp, event) in original.events().unwrap() {
+        rebuilt.add_event(timestamp, event);
+    }
+
+    assert_eq!(names(&rebuilt), names(&original));
+    assert_eq!(rebuilt.sorted_bars(), original.sorted_bars());
+    assert_eq!(rebuilt.stats(), original.stats());
+    assert_eq!(rebuilt.overflow_timestamps(), [150]);
+    assert_eq!(rebuilt.open_activations(), 1);
+    assert_eq!(rebuilt.range(), original.range());
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::model;
+use rtic_scope_frontend_racer::{trace::TraceModel, vcd};
+
+fn write(model: &mut TraceModel) -> String {
+    let mut out = vec![];
+    vcd::write(model, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Returns the value changes after the initial dump.
+fn changes(vcd: &str) -> Vec<&str> {
+    let (_, changes) = vcd.split_once("$dumpvars").unwrap();
+    let (_, changes) = changes.split_once("$end\n").unwrap();
+    changes.lines().collect()
+}
+
+#[test]
+fn nested_tasks() {
+    let mut model = model(&[("app::low", 100, 400), ("app::high", 200, 300)]);
+
+    let vcd = write(&mut model);
+    assert!(vcd.contains("$timescale 1ns $end"));
+    assert!(vcd.contains("$var wire 1 ! app__low $end"), "{}", vcd);
+    assert!(vcd.contains("$var wire 1 \" app__high $end"), "{}", vcd);
+    assert!(vcd.contains("$var integer 32 # active_priority $end"));
+
+    // Without known priorities, the nesting depth is written instead.
+    assert_eq!(
+        changes(&vcd),
+        [
+            "#100", "1!", "b1 #", "#200", "1\"", "b10 #", "#300", "0\"", "b1 #", "#400", "0!",
+            "b0 #",
+        ]
+    );
+}
+
+#[test]
+fn known_priorities() {
+    let mut model = model(&[("app::low", 100, 400), ("app::high", 200, 300)]);
+    model.set_priority(0, Some(1));
+    model.set_priority(1, Some(3));
+
+    assert_eq!(
+        changes(&write(&mut model)),
+        [
+            "#100", "1!", "b1 #", "#200", "1\"", "b11 #", "#300", "0\"", "b1 #", "#400", "0!",
+            "b0 #",
+        ]
+    );
+}
+
+#[test]
+fn back_to_back_activations() {
+    let mut model = model(&[("app::foo", 100, 200), ("app::foo", 200, 300)]);
+
+    // The wire stays high in between.
+    assert_eq!(
+        changes(&write(&mut model)),
+        ["#100", "1!", "b1 \"", "#300", "0!", "b0 \""]
+    );
+}